@@ -0,0 +1,92 @@
+// Consensus encoding of the blockchain types, specified in
+// docs/consensus-encoding.md.
+//
+// The encodings are derived on the types, block headers and transactions
+// start with a version byte, so their layout can be extended without
+// changing the hashes of existing blocks.
+
+/// The only supported version of the header and transaction encodings.
+pub const ENCODING_VERSION: u8 = 1;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Block, BlockHeader, CompactTarget, TxData, Wallet};
+    use zgc_common::encoding::{Decode, Encode};
+    use zgc_common::{Address, Amount, Error, Timestamp, H256};
+
+    fn block() -> Block {
+        let alice = Wallet::new(String::from("alice")).unwrap();
+        let tx = alice
+            .new_transaction(
+                Amount::from_base_units(10).unwrap(),
+                Address::new([0xaa; 20]),
+                String::from("alice"),
+            )
+            .unwrap();
+
+        let header = BlockHeader {
+            height: 1,
+            created_at: Timestamp::from_unix_secs(1_600_000_000).unwrap(),
+            previous_hash: H256::new([0x11; 32]),
+            merkle_root: H256::zero(),
+            target: CompactTarget::from_bits(0x1d00_ffff),
+            nonce: 42,
+            extra_nonce: 7,
+        };
+        let coinbase = TxData::coinbase(alice.address(), Amount::from_zgc(50).unwrap());
+        Block::new(header, vec![coinbase, tx])
+    }
+
+    #[test]
+    fn header_layout() {
+        let header = block().header;
+        let bytes = header.to_bytes();
+
+        let mut expected = vec![ENCODING_VERSION];
+        expected.extend_from_slice(&1_u64.to_le_bytes());
+        expected.extend_from_slice(&1_600_000_000_000_u64.to_le_bytes());
+        expected.extend_from_slice(&[0x11; 32]);
+        expected.extend_from_slice(header.merkle_root.as_bytes());
+        expected.extend_from_slice(&0x1d00_ffff_u32.to_le_bytes());
+        expected.extend_from_slice(&42_u32.to_le_bytes());
+        expected.extend_from_slice(&7_u64.to_le_bytes());
+        assert_eq!(bytes, expected);
+        assert_eq!(BlockHeader::from_bytes(&bytes), Ok(header));
+    }
+
+    #[test]
+    fn transaction_layout() {
+        let tx = block().transactions.remove(1);
+        let bytes = tx.to_bytes();
+
+        // version, sender, recipient, amount, threshold, 1 public key and 1
+        // signature
+        assert_eq!(bytes.len(), 1 + 20 + 20 + 8 + 1 + (4 + 33) + (4 + 1 + 64));
+        assert_eq!(bytes[0], ENCODING_VERSION);
+        assert_eq!(&bytes[21..41], &[0xaa; 20]);
+        assert_eq!(&bytes[41..49], &10_u64.to_le_bytes());
+        assert_eq!(TxData::from_bytes(&bytes), Ok(tx));
+    }
+
+    #[test]
+    fn block_round_trip() {
+        let block = block();
+        let bytes = block.to_bytes();
+        assert_eq!(Block::from_bytes(&bytes), Ok(block));
+
+        for len in 0..bytes.len() {
+            assert_eq!(Block::from_bytes(&bytes[..len]), Err(Error::UnexpectedEnd));
+        }
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut bytes = block().header.to_bytes();
+        bytes[0] = 2;
+        assert_eq!(
+            BlockHeader::from_bytes(&bytes),
+            Err(Error::UnsupportedVersion(2))
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+/// Errors of wallets, transactions and the blockchain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Parsing or decoding a common type failed.
+    Common(zgc_common::Error),
+    /// A cryptographic operation or an address encoding failed.
+    Crypto(zgc_crypto::Error),
+    /// The private key doesn't belong to the wallet.
+    WrongPrivateKey,
+    /// The wallet's public key is not among the signers of the transaction.
+    NotASigner,
+    /// The version byte of a WIF private key belongs to no network.
+    UnknownPrivateKeyVersion(u8),
+    /// The byte after a WIF private key is not the compression flag.
+    InvalidCompressionFlag(u8),
+    /// The WIF private key is for an uncompressed public key.
+    UncompressedPrivateKey,
+    /// A multisig has no public keys or more than the maximum.
+    InvalidKeyCount(usize),
+    /// The multisig threshold is zero or larger than the number of keys.
+    InvalidThreshold { threshold: u8, keys: usize },
+    /// A public key occurs more than once in a multisig.
+    DuplicatePublicKeys,
+    /// Signatures of different transactions cannot be combined.
+    DifferentTransactions,
+    /// The sender address is not the address of the transaction's signers.
+    SenderMismatch,
+    /// The signer with the given index signed more than once.
+    DuplicateSignature(u8),
+    /// The signer index is not an index into the multisig keys.
+    UnknownSigner(u8),
+    /// The signature of the signer with the given index is invalid.
+    InvalidSignature(u8),
+    /// Fewer valid signatures than the multisig threshold.
+    NotEnoughSignatures { found: usize, threshold: u8 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Common(e) => write!(f, "invalid input: {}", e),
+            Self::Crypto(e) => write!(f, "cryptographic operation failed: {}", e),
+            Self::WrongPrivateKey => f.write_str("wrong private key provided"),
+            Self::NotASigner => f.write_str("wallet is not a signer of the transaction"),
+            Self::UnknownPrivateKeyVersion(version) => {
+                write!(f, "unknown private key version 0x{:02x}", version)
+            }
+            Self::InvalidCompressionFlag(flag) => {
+                write!(f, "invalid compression flag 0x{:02x}, expected 0x01", flag)
+            }
+            Self::UncompressedPrivateKey => {
+                f.write_str("uncompressed private keys are not supported")
+            }
+            Self::InvalidKeyCount(count) => write!(
+                f,
+                "multisig has {} public keys, expected 1 to {}",
+                count,
+                crate::MAX_MULTISIG_KEYS
+            ),
+            Self::InvalidThreshold { threshold, keys } => write!(
+                f,
+                "multisig threshold is {}, expected 1 to {}",
+                threshold, keys
+            ),
+            Self::DuplicatePublicKeys => f.write_str("multisig contains duplicate public keys"),
+            Self::DifferentTransactions => {
+                f.write_str("cannot combine signatures of different transactions")
+            }
+            Self::SenderMismatch => {
+                f.write_str("sender address does not match the signers of the transaction")
+            }
+            Self::DuplicateSignature(signer) => {
+                write!(f, "duplicate signature of signer {}", signer)
+            }
+            Self::UnknownSigner(signer) => write!(f, "unknown signer {}", signer),
+            Self::InvalidSignature(signer) => write!(f, "invalid signature of signer {}", signer),
+            Self::NotEnoughSignatures { found, threshold } => write!(
+                f,
+                "transaction has {} valid signatures, expected at least {}",
+                found, threshold
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Common(e) => Some(e),
+            Self::Crypto(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<zgc_common::Error> for Error {
+    fn from(e: zgc_common::Error) -> Self {
+        Self::Common(e)
+    }
+}
+
+// common errors are never nested in a crypto error, so matching on them
+// doesn't depend on which crate they passed through
+impl From<zgc_crypto::Error> for Error {
+    fn from(e: zgc_crypto::Error) -> Self {
+        match e {
+            zgc_crypto::Error::Common(e) => Self::Common(e),
+            e => Self::Crypto(e),
+        }
+    }
+}
@@ -1,38 +1,299 @@
-use std::collections::HashMap;
-use zgc_common::{Address, H256};
+mod encoding;
+mod error;
+pub mod merkle;
+mod miner;
+mod orphan;
+mod params;
+mod pow;
+mod private_key;
+mod rejection;
+mod reorg;
+mod retarget;
+pub mod vanity;
+
+use std::collections::{HashMap, HashSet};
+use std::convert::{TryFrom, TryInto};
+use zgc_common::encoding::{Decode, Encode};
+use zgc_common::{Address, Amount, Clock, Network, PublicKey, Signature, Timestamp, H256, U256};
+use zgc_crypto::address::EncodedAddress;
 use zgc_crypto::{Hasher, Sha256};
 
 use serde::{Deserialize, Serialize};
 
-pub struct Blockchain<'a> {
-    height2hash: HashMap<usize, &'a str>,
-    hash2block: HashMap<&'a str, Block>,
+pub use encoding::ENCODING_VERSION;
+pub use error::Error;
+pub use miner::{Miner, MiningProgress};
+pub use orphan::{OrphanPool, MAX_ORPHANS, MAX_ORPHAN_AGE};
+pub use params::Params;
+pub use pow::CompactTarget;
+pub use private_key::PrivateKey;
+pub use rejection::{Rejection, MAX_FUTURE_DRIFT};
+pub use reorg::Reorg;
+pub use retarget::Retarget;
+
+/// Maximal number of public keys a multisig account can commit to.
+pub const MAX_MULTISIG_KEYS: usize = 16;
+
+/// A tree of blocks rooted in the genesis block.
+///
+/// The canonical chain is the branch with the most cumulative work, or the
+/// longest branch if proof of work is disabled. Of two branches with the
+/// same work, the one that got there first stays canonical.
+pub struct Blockchain {
+    params: Params,
+    /// The blocks of the canonical chain.
+    height2hash: HashMap<usize, H256>,
+    hash2block: HashMap<H256, Block>,
+    /// The cumulative work of the branch ending in a block.
+    hash2work: HashMap<H256, U256>,
+    tip: H256,
+    orphans: OrphanPool,
 }
 
-impl Blockchain<'_> {
+impl Blockchain {
     pub fn new(hasher: &impl Hasher) -> Self {
+        Self::with_params(Params::default(), hasher)
+    }
+
+    pub fn with_params(params: Params, hasher: &impl Hasher) -> Self {
+        let genesis = Block::genesis(&params);
+        let hash = genesis.hash(hasher);
+        let orphans = OrphanPool::new(params.max_orphans, params.max_orphan_age);
         let mut bc = Self {
+            params,
             height2hash: HashMap::new(),
             hash2block: HashMap::new(),
+            hash2work: HashMap::new(),
+            tip: hash,
+            orphans,
         };
-        bc.insert(Block::genesis(), hasher);
+        bc.height2hash.insert(0, hash);
+        bc.hash2work.insert(hash, bc.work(&genesis));
+        bc.hash2block.insert(hash, genesis);
         bc
     }
 
-    pub fn insert(&mut self, block: Block, hasher: &impl Hasher) {
-        let header_string = format!(
-            "{},{:?},{}",
-            block.header.created_at, block.header.previous_hash, block.header.nonce
-        );
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Validates the block against its parent and stores it, together
+    /// with the orphans that were waiting for it.
+    ///
+    /// Returns how the canonical chain changed, see [`Reorg`]. A block whose
+    /// parent is unknown is rejected with [`Rejection::UnknownParent`], but
+    /// kept in the orphan pool until the parent is inserted.
+    pub fn insert(
+        &mut self,
+        block: Block,
+        hasher: &impl Hasher,
+        clock: &impl Clock,
+    ) -> Result<Reorg, Rejection> {
+        if let Err(rejection) = self.validate(&block, hasher, clock) {
+            if let Rejection::UnknownParent(_) = rejection {
+                self.orphans.insert(block.hash(hasher), block, clock.now());
+            }
+            return Err(rejection);
+        }
+
+        let mut parents = vec![block.hash(hasher)];
+        let mut reorg = self.store(block, hasher);
+        while let Some(parent) = parents.pop() {
+            for orphan in self.orphans.take_children(&parent) {
+                // invalid orphans are dropped
+                if self.validate(&orphan, hasher, clock).is_ok() {
+                    parents.push(orphan.hash(hasher));
+                    reorg.append(self.store(orphan, hasher));
+                }
+            }
+        }
+        self.orphans.evict_expired(clock.now());
+        Ok(reorg)
+    }
+
+    /// The blocks that arrived before their parent.
+    pub fn orphans(&self) -> &OrphanPool {
+        &self.orphans
+    }
+
+    fn validate(
+        &self,
+        block: &Block,
+        hasher: &impl Hasher,
+        clock: &impl Clock,
+    ) -> Result<(), Rejection> {
+        let hash = block.hash(hasher);
+        if self.hash2block.contains_key(&hash) {
+            return Err(Rejection::Duplicate(hash));
+        }
+
+        if let Some(limit) = self.params.pow_limit {
+            let target = block.header.target;
+            match target.to_target() {
+                Some(expanded) if expanded <= limit => (),
+                _ => return Err(Rejection::InvalidTarget(target)),
+            }
+            if !target.is_met_by(&hash) {
+                return Err(Rejection::InsufficientWork { target, hash });
+            }
+        }
+
+        let parent = self
+            .hash2block
+            .get(&block.header.previous_hash)
+            .ok_or(Rejection::UnknownParent(block.header.previous_hash))?;
+        if block.header.height != parent.header.height + 1 {
+            return Err(Rejection::InvalidHeight {
+                expected: parent.header.height + 1,
+                found: block.header.height,
+            });
+        }
+
+        if self.params.pow_limit.is_some() {
+            let expected = self.expected_target(parent);
+            if block.header.target != expected {
+                return Err(Rejection::UnexpectedTarget {
+                    expected,
+                    found: block.header.target,
+                });
+            }
+        }
+
+        let created_at = block.header.created_at;
+        if created_at <= parent.header.created_at {
+            return Err(Rejection::TimestampNotAfterParent {
+                parent: parent.header.created_at,
+                found: created_at,
+            });
+        }
+        let maximum = clock
+            .now()
+            .checked_add(MAX_FUTURE_DRIFT)
+            .unwrap_or_else(|| Timestamp::from_unix_millis(u64::MAX));
+        if created_at > maximum {
+            return Err(Rejection::TimestampInFuture {
+                maximum,
+                found: created_at,
+            });
+        }
+
+        self.validate_transactions(block)
+    }
+
+    /// Checks the limits, the coinbase and the signatures of the block's
+    /// transactions and that the header commits to them.
+    fn validate_transactions(&self, block: &Block) -> Result<(), Rejection> {
+        let count = block.transactions.len();
+        if count > self.params.max_block_transactions {
+            return Err(Rejection::TooManyTransactions {
+                maximum: self.params.max_block_transactions,
+                found: count,
+            });
+        }
+        let size = block.to_bytes().len();
+        if size > self.params.max_block_size {
+            return Err(Rejection::TooLarge {
+                maximum: self.params.max_block_size,
+                found: size,
+            });
+        }
+
+        let merkle_root = block.merkle_root();
+        if block.header.merkle_root != merkle_root {
+            return Err(Rejection::InvalidMerkleRoot {
+                expected: merkle_root,
+                found: block.header.merkle_root,
+            });
+        }
 
-        let hash = Box::leak(Box::new(hasher.digest(header_string).to_string()));
+        match block.transactions.first() {
+            Some(coinbase) if coinbase.is_coinbase() => {
+                if *coinbase != TxData::coinbase(coinbase.recipient, coinbase.amount) {
+                    return Err(Rejection::InvalidCoinbase);
+                }
+            }
+            _ => return Err(Rejection::MissingCoinbase),
+        }
 
-        self.height2hash.insert(block.height, hash);
+        for (index, tx) in block.transactions.iter().enumerate().skip(1) {
+            if tx.is_coinbase() {
+                return Err(Rejection::MisplacedCoinbase(index));
+            }
+            tx.verify()
+                .map_err(|source| Rejection::InvalidTransaction { index, source })?;
+        }
+        Ok(())
+    }
+
+    /// Stores a validated block and makes it the tip if its branch has more
+    /// work than the canonical chain.
+    fn store(&mut self, block: Block, hasher: &impl Hasher) -> Reorg {
+        let hash = block.hash(hasher);
+        // validation made sure that the parent is stored
+        let work = self.hash2work[&block.header.previous_hash].saturating_add(self.work(&block));
+        self.hash2work.insert(hash, work);
         self.hash2block.insert(hash, block);
+
+        if work > self.hash2work[&self.tip] {
+            self.reorganize(hash)
+        } else {
+            Reorg::default()
+        }
     }
 
-    pub fn find_hash(&self, hash: &str) -> Option<&Block> {
-        self.hash2block.get(hash)
+    /// Makes `tip` the tip of the canonical chain.
+    fn reorganize(&mut self, tip: H256) -> Reorg {
+        // walk back from the new tip until the branch joins the chain
+        let mut connected = Vec::new();
+        let mut hash = tip;
+        loop {
+            let block = &self.hash2block[&hash];
+            if self.height2hash.get(&block.header.height) == Some(&hash) {
+                break;
+            }
+            connected.push(hash);
+            hash = block.header.previous_hash;
+        }
+        connected.reverse();
+
+        let fork_height = self.hash2block[&hash].header.height;
+        let tip_height = self.hash2block[&self.tip].header.height;
+        let disconnected = (fork_height + 1..=tip_height)
+            .rev()
+            .filter_map(|height| self.height2hash.remove(&height))
+            .collect();
+        for hash in &connected {
+            self.height2hash
+                .insert(self.hash2block[hash].header.height, *hash);
+        }
+        self.tip = tip;
+
+        Reorg {
+            disconnected,
+            connected,
+        }
+    }
+
+    /// The work of a single block: the expected number of hashes needed to
+    /// meet its target, or 1 if proof of work is disabled, so the longest
+    /// branch wins.
+    fn work(&self, block: &Block) -> U256 {
+        if self.params.pow_limit.is_some() {
+            block.header.target.work()
+        } else {
+            U256::ONE
+        }
+    }
+
+    /// The cumulative work of the canonical chain.
+    pub fn chain_work(&self) -> U256 {
+        self.hash2work[&self.tip]
+    }
+
+    /// Looks up a block by its hash, given as an [`H256`] or a hex string.
+    /// Strings that are not a valid hash find nothing.
+    pub fn find_hash<H: TryInto<H256>>(&self, hash: H) -> Option<&Block> {
+        self.hash2block.get(&hash.try_into().ok()?)
     }
 
     pub fn find_height(&self, height: usize) -> Option<&Block> {
@@ -43,107 +304,1147 @@ impl Blockchain<'_> {
         }
     }
 
+    /// The tip of the canonical chain.
     pub fn last(&self) -> Option<&Block> {
-        self.find_height(self.hash2block.len() - 1)
+        self.hash2block.get(&self.tip)
+    }
+
+    /// The target of a block on top of `parent`, computed from the
+    /// timestamps and targets of its ancestors, see [`Retarget`].
+    fn expected_target(&self, parent: &Block) -> CompactTarget {
+        let limit = match self.params.pow_limit {
+            Some(limit) => limit,
+            None => return self.params.limit_target(),
+        };
+
+        let height = parent.header.height + 1;
+        let mut ancestors: Vec<_> = std::iter::successors(Some(parent), |block| {
+            self.hash2block.get(&block.header.previous_hash)
+        })
+        .take(self.params.retarget.ancestors(height))
+        .map(|block| {
+            let target = block.header.target.to_target().unwrap_or(limit);
+            (block.header.created_at, target)
+        })
+        .collect();
+        ancestors.reverse();
+
+        let target =
+            self.params
+                .retarget
+                .next_target(height, self.params.target_spacing, limit, &ancestors);
+        CompactTarget::from_target(target)
+    }
+
+    /// Creates a block with the given transactions on top of the last
+    /// block, timestamped by `clock`. The first transaction has to be the
+    /// coinbase.
+    pub fn next_block(
+        &self,
+        transactions: Vec<TxData>,
+        clock: &impl Clock,
+        hasher: &impl Hasher,
+    ) -> Block {
+        // the genesis block is always there
+        let last = self.last().expect("blockchain without genesis block");
+        self.block_on(last, transactions, clock, hasher)
+    }
+
+    fn block_on(
+        &self,
+        parent: &Block,
+        transactions: Vec<TxData>,
+        clock: &impl Clock,
+        hasher: &impl Hasher,
+    ) -> Block {
+        Block::new(
+            BlockHeader {
+                height: parent.header.height + 1,
+                created_at: clock.now(),
+                previous_hash: parent.hash(hasher),
+                merkle_root: H256::zero(),
+                target: self.expected_target(parent),
+                nonce: 0,
+                extra_nonce: 0,
+            },
+            transactions,
+        )
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Encode, Decode, Default, Debug, Clone, PartialEq, Eq)]
 pub struct Block {
-    height: usize,
     header: BlockHeader,
-    data: TxData,
+    transactions: Vec<TxData>,
 }
 
 impl Block {
-    fn genesis() -> Self {
-        Block {
-            height: 0,
-            header: BlockHeader {
-                created_at: 0,
-                previous_hash: H256::zero(),
-                nonce: 0,
-            },
-            data: TxData {
-                signature: H256::zero(),
-                sender: Address::zero(),
-                recipient: Address::zero(),
-                amount: 0,
-            },
-        }
+    /// Creates a block and commits to its transactions in the header.
+    fn new(header: BlockHeader, transactions: Vec<TxData>) -> Self {
+        let mut block = Self {
+            header,
+            transactions,
+        };
+        block.header.merkle_root = block.merkle_root();
+        block
+    }
+
+    fn genesis(params: &Params) -> Self {
+        let coinbase = TxData::coinbase(Address::zero(), Amount::ZERO);
+        let header = BlockHeader {
+            target: params.limit_target(),
+            ..BlockHeader::default()
+        };
+        Self::new(header, vec![coinbase])
+    }
+
+    /// The target the hash of the block has to meet.
+    pub fn target(&self) -> CompactTarget {
+        self.header.target
+    }
+
+    pub fn transactions(&self) -> &[TxData] {
+        &self.transactions
+    }
+
+    /// The hash of the block, i.e. the digest of the encoded header.
+    pub fn hash(&self, hasher: &impl Hasher) -> H256 {
+        hasher.digest_bytes(&self.header.to_bytes())
+    }
+
+    /// The Merkle root of the block's transactions, which the header has to
+    /// commit to.
+    pub fn merkle_root(&self) -> H256 {
+        self.merkle_tree().root()
+    }
+
+    /// Proves that the transaction at `index` is part of the block, see
+    /// [`merkle::Proof::verify`] with the transaction hash and the Merkle
+    /// root of the header.
+    pub fn transaction_proof(&self, index: usize) -> Option<merkle::Proof> {
+        self.merkle_tree().proof(index)
+    }
+
+    fn merkle_tree(&self) -> merkle::MerkleTree {
+        let leaves: Vec<H256> = self.transactions.iter().map(TxData::hash).collect();
+        merkle::MerkleTree::new(&leaves)
     }
 }
 
 #[test]
 fn genesis_default() {
-    assert_eq!(Block::genesis(), Block::default());
+    let params = Params::default();
+    let genesis = Block::genesis(&params);
+    assert_eq!(genesis.header.merkle_root, genesis.merkle_root());
+    assert_eq!(genesis.target(), CompactTarget::from_target(U256::MAX));
+
+    let default = Block::default();
+    assert_eq!(genesis.transactions, vec![TxData::default()]);
+    assert!(default.transactions.is_empty());
+    assert_eq!(
+        genesis.header,
+        BlockHeader {
+            merkle_root: genesis.merkle_root(),
+            target: params.limit_target(),
+            ..default.header
+        }
+    );
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Encode, Decode, Default, Debug, Clone, PartialEq, Eq)]
+#[encoding(version = ENCODING_VERSION)]
 struct BlockHeader {
-    created_at: u64,
+    /// The number of ancestors of the block.
+    height: usize,
+    created_at: Timestamp,
     previous_hash: H256,
+    merkle_root: H256,
+    target: CompactTarget,
     nonce: u32,
+    /// Changed by miners once all values of `nonce` are exhausted.
+    extra_nonce: u64,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Encode, Decode, Default, Debug, Clone, PartialEq, Eq)]
+#[encoding(version = ENCODING_VERSION)]
 pub struct TxData {
-    signature: H256,
-    sender: Address,
-    recipient: Address,
-    amount: u64,
+    sender: EncodedAddress,
+    recipient: EncodedAddress,
+    amount: Amount,
+    signers: Multisig,
+    signatures: Vec<TxSignature>,
+}
+
+impl TxData {
+    /// The first transaction of a block, which pays the block reward to
+    /// `recipient`. It has no sender and no signers.
+    pub fn coinbase(recipient: impl Into<EncodedAddress>, amount: Amount) -> Self {
+        let recipient = recipient.into();
+        Self {
+            sender: EncodedAddress::new(Address::zero(), recipient.encoding),
+            recipient,
+            amount,
+            signers: Multisig::default(),
+            signatures: Vec::new(),
+        }
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        self.signers.public_keys.is_empty()
+    }
+
+    /// The sender is displayed in the encoding of the recipient.
+    fn unsigned(signers: Multisig, recipient: EncodedAddress, amount: Amount) -> Self {
+        Self {
+            sender: EncodedAddress::new(signers.address(), recipient.encoding),
+            recipient,
+            amount,
+            signers,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// The hash of the whole transaction including the signatures, the
+    /// leaf of the transaction in the Merkle tree of a block.
+    pub fn hash(&self) -> H256 {
+        Sha256::new().digest_bytes(&self.to_bytes())
+    }
+
+    /// The digest that every signer of the transaction signs, i.e. the
+    /// digest of the transaction without any signatures.
+    pub fn signing_hash(&self) -> H256 {
+        let unsigned = Self {
+            signers: self.signers.clone(),
+            signatures: Vec::new(),
+            ..*self
+        };
+        Sha256::new().digest_bytes(&unsigned.to_bytes())
+    }
+
+    /// Returns true if the transaction carries enough valid signatures to be
+    /// accepted by a [`Blockchain`].
+    pub fn is_fully_signed(&self) -> bool {
+        self.verify().is_ok()
+    }
+
+    /// Merges the signatures of another copy of the same transaction into
+    /// this one.
+    ///
+    /// Every co-signer of a multisig account signs their own copy of a
+    /// partially signed transaction, and the copies are combined until
+    /// enough signatures are collected.
+    pub fn combine(&mut self, other: TxData) -> Result<(), Error> {
+        if self.signers != other.signers || self.signing_hash() != other.signing_hash() {
+            return Err(Error::DifferentTransactions);
+        }
+
+        for signature in other.signatures {
+            if !self.signatures.iter().any(|s| s.signer == signature.signer) {
+                self.signatures.push(signature);
+            }
+        }
+        self.signatures.sort_by_key(|s| s.signer);
+
+        Ok(())
+    }
+
+    /// Checks that the sender address commits to the signers of the
+    /// transaction and that at least `threshold` distinct signers have
+    /// signed it.
+    pub fn verify(&self) -> Result<(), Error> {
+        self.signers.validate()?;
+
+        if self.signers.address() != self.sender.address {
+            return Err(Error::SenderMismatch);
+        }
+
+        let message = self.signing_hash();
+        let mut signers = HashSet::new();
+
+        for signature in &self.signatures {
+            if !signers.insert(signature.signer) {
+                return Err(Error::DuplicateSignature(signature.signer));
+            }
+
+            let public_key = self
+                .signers
+                .public_keys
+                .get(usize::from(signature.signer))
+                .ok_or(Error::UnknownSigner(signature.signer))?;
+
+            if !zgc_crypto::verify(public_key, &message, &signature.signature) {
+                return Err(Error::InvalidSignature(signature.signer));
+            }
+        }
+
+        if signers.len() < usize::from(self.signers.threshold) {
+            return Err(Error::NotEnoughSignatures {
+                found: signers.len(),
+                threshold: self.signers.threshold,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// A signature together with the index of the signer's public key in the
+/// [`Multisig`] of the transaction.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxSignature {
+    signer: u8,
+    signature: Signature,
+}
+
+/// Spending policy of an account: at least `threshold` of `public_keys` have
+/// to sign a transaction.
+///
+/// The address of the account is derived from the policy, so the sender of a
+/// transaction cannot swap the keys without changing the address. Single
+/// key accounts are simply 1-of-1 multisig accounts.
+#[derive(Serialize, Deserialize, Encode, Decode, Default, Debug, Clone, PartialEq, Eq)]
+pub struct Multisig {
+    threshold: u8,
+    public_keys: Vec<PublicKey>,
+}
+
+impl Multisig {
+    pub fn new(threshold: u8, public_keys: Vec<PublicKey>) -> Result<Self, Error> {
+        let multisig = Self {
+            threshold,
+            public_keys,
+        };
+        multisig.validate()?;
+        Ok(multisig)
+    }
+
+    pub fn single(public_key: PublicKey) -> Self {
+        Self {
+            threshold: 1,
+            public_keys: vec![public_key],
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        let hasher = Sha256::new();
+        let hash_result = hasher.digest_bytes(&self.to_bytes());
+
+        // unwrap is fine because a H256 always has more than 20 bytes
+        Address::try_from(&hash_result.as_bytes()[..20]).unwrap()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.public_keys.is_empty() || self.public_keys.len() > MAX_MULTISIG_KEYS {
+            return Err(Error::InvalidKeyCount(self.public_keys.len()));
+        }
+
+        if self.threshold == 0 || usize::from(self.threshold) > self.public_keys.len() {
+            return Err(Error::InvalidThreshold {
+                threshold: self.threshold,
+                keys: self.public_keys.len(),
+            });
+        }
+
+        let unique_keys = self.public_keys.iter().collect::<HashSet<_>>();
+        if unique_keys.len() != self.public_keys.len() {
+            return Err(Error::DuplicatePublicKeys);
+        }
+
+        Ok(())
+    }
 }
 
 pub struct Wallet {
-    public_key: Address,
+    public_key: PublicKey,
 }
 
 impl Wallet {
-    pub fn new(private_key: String) -> Self {
-        Self {
-            public_key: keygen(private_key),
+    /// Fails if the private key is not a valid secp256k1 scalar, which
+    /// only happens with negligible probability for keys derived from a
+    /// phrase.
+    pub fn new(private_key: impl Into<PrivateKey>) -> Result<Self, Error> {
+        Ok(Self {
+            public_key: keygen(&private_key.into())?,
+        })
+    }
+
+    /// Imports a private key in Wallet Import Format (WIF).
+    ///
+    /// Wallets don't store private keys, so the decoded key is returned
+    /// next to the wallet and has to be provided whenever signing.
+    pub fn import_private_key(wif: &str, network: Network) -> Result<(Self, PrivateKey), Error> {
+        let private_key = PrivateKey::from_wif(wif, network)?;
+        Ok((Self::new(private_key)?, private_key))
+    }
+
+    /// Exports the private key of this wallet in Wallet Import Format (WIF).
+    pub fn export_private_key(
+        &self,
+        private_key: impl Into<PrivateKey>,
+        network: Network,
+    ) -> Result<String, Error> {
+        let private_key = private_key.into();
+        if self.public_key != keygen(&private_key)? {
+            return Err(Error::WrongPrivateKey);
         }
+
+        Ok(private_key.to_wif(network))
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    pub fn address(&self) -> Address {
+        Multisig::single(self.public_key).address()
     }
 
     pub fn new_transaction(
         &self,
-        amount: u64,
-        recipient: Address,
-        private_key: String,
-    ) -> Result<TxData, String> {
-        if self.public_key != keygen(private_key.clone()) {
-            return Err("Wrong private key provided, cannot sign transaction".to_string());
+        amount: Amount,
+        recipient: impl Into<EncodedAddress>,
+        private_key: impl Into<PrivateKey>,
+    ) -> Result<TxData, Error> {
+        self.new_multisig_transaction(
+            Multisig::single(self.public_key),
+            amount,
+            recipient,
+            private_key,
+        )
+    }
+
+    /// Creates a transaction spending from the account of `signers` and
+    /// signs it with the key of this wallet.
+    ///
+    /// Unless the threshold is one, the returned transaction is only
+    /// partially signed. It can be passed around (e.g. serialized) to the
+    /// other signers who add their signatures via [`Wallet::sign`], and the
+    /// copies can be merged with [`TxData::combine`].
+    pub fn new_multisig_transaction(
+        &self,
+        signers: Multisig,
+        amount: Amount,
+        recipient: impl Into<EncodedAddress>,
+        private_key: impl Into<PrivateKey>,
+    ) -> Result<TxData, Error> {
+        signers.validate()?;
+
+        let mut tx = TxData::unsigned(signers, recipient.into(), amount);
+        self.sign(&mut tx, private_key)?;
+        Ok(tx)
+    }
+
+    /// Adds the signature of this wallet to a (partially signed) transaction.
+    pub fn sign(&self, tx: &mut TxData, private_key: impl Into<PrivateKey>) -> Result<(), Error> {
+        let private_key = private_key.into();
+        if self.public_key != keygen(&private_key)? {
+            return Err(Error::WrongPrivateKey);
         }
 
-        let tx_header = format!(
-            "{},{:?},{:?},{}",
-            amount, self.public_key, recipient, private_key
-        );
+        let signer = tx
+            .signers
+            .public_keys
+            .iter()
+            .position(|key| *key == self.public_key)
+            .ok_or(Error::NotASigner)?;
 
-        let hasher = Sha256::new();
+        let signature = TxSignature {
+            // cast is fine because there are at most MAX_MULTISIG_KEYS signers
+            signer: signer as u8,
+            signature: zgc_crypto::sign(private_key.secret(), &tx.signing_hash())?,
+        };
 
-        Ok(TxData {
-            signature: hasher.digest(tx_header),
-            sender: self.public_key,
-            recipient,
-            amount,
+        tx.signatures.retain(|s| s.signer != signature.signer);
+        tx.signatures.push(signature);
+        tx.signatures.sort_by_key(|s| s.signer);
+
+        Ok(())
+    }
+}
+
+fn keygen(private_key: &PrivateKey) -> Result<PublicKey, Error> {
+    Ok(zgc_crypto::derive_public_key(private_key.secret())?)
+}
+
+#[test]
+fn test_keygen() {
+    let wallet = Wallet::new(String::from("random2private#key")).unwrap();
+    assert_eq!(
+        wallet.address().to_string(),
+        "97da8580eea7ae32d603fb88a20648acff67eac4"
+    );
+}
+
+#[test]
+fn private_key_import_export() {
+    use std::error::Error as _;
+
+    let wallet = Wallet::new(String::from("alice")).unwrap();
+
+    assert_eq!(
+        wallet.export_private_key(String::from("bob"), Network::Mainnet),
+        Err(Error::WrongPrivateKey)
+    );
+
+    let wif = wallet
+        .export_private_key(String::from("alice"), Network::Testnet)
+        .expect("failed to export private key");
+    let (imported, private_key) =
+        Wallet::import_private_key(&wif, Network::Testnet).expect("failed to import");
+    assert_eq!(imported.address(), wallet.address());
+    let error = Wallet::import_private_key(&wif, Network::Mainnet)
+        .err()
+        .unwrap();
+    assert_eq!(
+        error,
+        Error::Common(zgc_common::Error::WrongNetwork {
+            expected: Network::Mainnet,
+            found: Network::Testnet
+        })
+    );
+    assert_eq!(
+        error.to_string(),
+        "invalid input: input belongs to testnet, expected mainnet"
+    );
+    assert_eq!(
+        error
+            .source()
+            .and_then(|e| e.downcast_ref::<zgc_common::Error>()),
+        Some(&zgc_common::Error::WrongNetwork {
+            expected: Network::Mainnet,
+            found: Network::Testnet
+        })
+    );
+
+    // the imported key can sign for the original wallet
+    let tx = wallet
+        .new_transaction(
+            Amount::from_base_units(10).unwrap(),
+            Address::zero(),
+            private_key,
+        )
+        .expect("failed to create transaction");
+    assert!(tx.is_fully_signed());
+}
+
+#[test]
+fn single_signature_transaction() {
+    use zgc_common::MockClock;
+
+    let hasher = Sha256::new();
+    let mut blockchain = Blockchain::new(&hasher);
+    let wallet = Wallet::new(String::from("alice")).unwrap();
+
+    let result = wallet.new_transaction(
+        Amount::from_base_units(10).unwrap(),
+        Address::zero(),
+        String::from("bob"),
+    );
+    assert_eq!(result, Err(Error::WrongPrivateKey));
+
+    let tx = wallet
+        .new_transaction(
+            Amount::from_base_units(10).unwrap(),
+            Address::zero(),
+            String::from("alice"),
+        )
+        .expect("failed to create transaction");
+    assert_eq!(tx.sender.address, wallet.address());
+    assert!(tx.is_fully_signed());
+
+    let now = Timestamp::from_unix_secs(1_600_000_000).unwrap();
+    let clock = MockClock::new(now);
+    let coinbase = TxData::coinbase(wallet.address(), Amount::from_zgc(50).unwrap());
+    let block = blockchain.next_block(vec![coinbase, tx], &clock, &hasher);
+    assert_eq!(block.header.height, 1);
+    assert_eq!(block.header.created_at, now);
+    assert_eq!(
+        block.header.previous_hash,
+        Block::genesis(&Params::default()).hash(&hasher)
+    );
+    let hash = block.hash(&hasher);
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Ok(Reorg {
+            disconnected: vec![],
+            connected: vec![hash]
+        })
+    );
+
+    let block = blockchain.find_height(1).unwrap();
+    assert_eq!(blockchain.find_hash(hash), Some(block));
+    assert_eq!(blockchain.find_hash(hash.to_string().as_str()), Some(block));
+    assert_eq!(
+        blockchain.find_hash(format!("{:#}", hash).as_str()),
+        Some(block)
+    );
+    assert_eq!(blockchain.find_hash("not a hash"), None);
+    assert_eq!(blockchain.find_hash(H256::zero()), None);
+    assert_eq!(blockchain.last(), Some(block));
+}
+
+#[test]
+fn multisig_transaction() {
+    use zgc_crypto::address::{AddressEncoding, AddressFormat};
+
+    let hasher = Sha256::new();
+    let mut blockchain = Blockchain::new(&hasher);
+
+    let alice = Wallet::new(String::from("alice")).unwrap();
+    let bob = Wallet::new(String::from("bob")).unwrap();
+    let carol = Wallet::new(String::from("carol")).unwrap();
+    let treasury = Multisig::new(
+        2,
+        vec![alice.public_key(), bob.public_key(), carol.public_key()],
+    )
+    .expect("invalid multisig");
+
+    let testnet = AddressEncoding::new(AddressFormat::Bech32m, Network::Testnet);
+    let mut tx = alice
+        .new_multisig_transaction(
+            treasury.clone(),
+            Amount::from_base_units(100).unwrap(),
+            EncodedAddress::new(bob.address(), testnet),
+            String::from("alice"),
+        )
+        .expect("failed to create transaction");
+    assert_eq!(tx.sender.address, treasury.address());
+    assert!(!tx.is_fully_signed());
+
+    // the partially signed transaction is passed around to the co-signers,
+    // the addresses keep the encoding of the recipient
+    let json = serde_json::to_string(&tx).unwrap();
+    assert!(json.contains(&EncodedAddress::new(treasury.address(), testnet).to_string()));
+    let mut carols_copy: TxData = serde_json::from_str(&json).unwrap();
+    assert_eq!(carols_copy.sender.encoding, testnet);
+    carol
+        .sign(&mut carols_copy, String::from("carol"))
+        .expect("failed to sign");
+    assert_eq!(carols_copy.signatures.len(), 2);
+
+    // signing twice doesn't produce a second signature
+    let mut alices_copy: TxData = serde_json::from_str(&json).unwrap();
+    alice
+        .sign(&mut alices_copy, String::from("alice"))
+        .expect("failed to sign");
+    assert_eq!(alices_copy.signatures.len(), 1);
+
+    let outsider = Wallet::new(String::from("dave")).unwrap();
+    assert_eq!(
+        outsider.sign(&mut alices_copy, String::from("dave")),
+        Err(Error::NotASigner)
+    );
+
+    tx.combine(carols_copy).expect("failed to combine");
+    assert!(tx.is_fully_signed());
+
+    let clock = zgc_common::MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+    let coinbase = TxData::coinbase(carol.address(), Amount::from_zgc(50).unwrap());
+    let block = blockchain.next_block(vec![coinbase, tx], &clock, &hasher);
+    blockchain.insert(block, &hasher, &clock).unwrap();
+}
+
+#[test]
+fn invalid_multisig_transaction() {
+    let alice = Wallet::new(String::from("alice")).unwrap();
+    let bob = Wallet::new(String::from("bob")).unwrap();
+    let treasury = Multisig::new(2, vec![alice.public_key(), bob.public_key()]).unwrap();
+
+    assert_eq!(
+        Multisig::new(0, vec![alice.public_key()]),
+        Err(Error::InvalidThreshold {
+            threshold: 0,
+            keys: 1
+        })
+    );
+    assert!(Multisig::new(2, vec![alice.public_key()]).is_err());
+    assert_eq!(
+        Multisig::new(1, vec![alice.public_key(), alice.public_key()]),
+        Err(Error::DuplicatePublicKeys)
+    );
+    assert_eq!(Multisig::new(1, vec![]), Err(Error::InvalidKeyCount(0)));
+
+    let mut tx = alice
+        .new_multisig_transaction(
+            treasury.clone(),
+            Amount::from_base_units(100).unwrap(),
+            Address::zero(),
+            String::from("alice"),
+        )
+        .unwrap();
+    assert_eq!(
+        tx.verify(),
+        Err(Error::NotEnoughSignatures {
+            found: 1,
+            threshold: 2
+        })
+    );
+
+    // the same signature counted twice
+    tx.signatures.push(tx.signatures[0]);
+    assert_eq!(tx.verify(), Err(Error::DuplicateSignature(0)));
+
+    // a valid signature of the wrong signer
+    tx.signatures[1].signer = 1;
+    assert_eq!(tx.verify(), Err(Error::InvalidSignature(1)));
+
+    // tampering with the amount invalidates the signatures
+    let mut tx = alice
+        .new_multisig_transaction(
+            treasury.clone(),
+            Amount::from_base_units(100).unwrap(),
+            Address::zero(),
+            String::from("alice"),
+        )
+        .unwrap();
+    bob.sign(&mut tx, String::from("bob")).unwrap();
+    assert_eq!(tx.verify(), Ok(()));
+    tx.amount = Amount::from_base_units(1000).unwrap();
+    assert_eq!(tx.verify(), Err(Error::InvalidSignature(0)));
+
+    // different transactions cannot be combined
+    let other = bob
+        .new_multisig_transaction(
+            treasury,
+            Amount::from_base_units(99).unwrap(),
+            Address::zero(),
+            String::from("bob"),
+        )
+        .unwrap();
+    assert_eq!(tx.combine(other), Err(Error::DifferentTransactions));
+
+    // the sender must commit to the signers
+    let mut tx = alice
+        .new_transaction(
+            Amount::from_base_units(100).unwrap(),
+            Address::zero(),
+            String::from("alice"),
+        )
+        .unwrap();
+    tx.sender = bob.address().into();
+    assert_eq!(tx.verify(), Err(Error::SenderMismatch));
+}
+
+#[test]
+fn invalid_block() {
+    use std::error::Error as _;
+    use std::time::Duration;
+    use zgc_common::MockClock;
+
+    let hasher = Sha256::new();
+    let mut blockchain = Blockchain::new(&hasher);
+    let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+    let alice = Wallet::new(String::from("alice")).unwrap();
+    let tx = || {
+        alice
+            .new_transaction(
+                Amount::from_base_units(10).unwrap(),
+                Address::zero(),
+                String::from("alice"),
+            )
+            .unwrap()
+    };
+    let coinbase = TxData::coinbase(alice.address(), Amount::from_zgc(50).unwrap());
+    let txs = || vec![coinbase.clone(), tx()];
+
+    // swapping a transaction changes the Merkle root
+    let mut block = blockchain.next_block(txs(), &clock, &hasher);
+    let found = block.header.merkle_root;
+    block.transactions[1].amount = Amount::from_base_units(20).unwrap();
+    let expected = block.merkle_root();
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::InvalidMerkleRoot { expected, found })
+    );
+
+    let mut tampered = tx();
+    tampered.amount = Amount::from_base_units(20).unwrap();
+    let block = blockchain.next_block(vec![coinbase.clone(), tampered], &clock, &hasher);
+    let rejection = blockchain.insert(block, &hasher, &clock).unwrap_err();
+    assert_eq!(
+        rejection,
+        Rejection::InvalidTransaction {
+            index: 1,
+            source: Error::InvalidSignature(0)
+        }
+    );
+    assert_eq!(rejection.to_string(), "transaction 1 is invalid");
+    assert_eq!(
+        rejection.source().map(|e| e.to_string()),
+        Some("invalid signature of signer 0".to_string())
+    );
+
+    let mut block = blockchain.next_block(txs(), &clock, &hasher);
+    block.header.previous_hash = H256::new([1; 32]);
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::UnknownParent(H256::new([1; 32])))
+    );
+
+    let mut block = blockchain.next_block(txs(), &clock, &hasher);
+    block.header.height = 2;
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::InvalidHeight {
+            expected: 1,
+            found: 2
+        })
+    );
+
+    let mut block = blockchain.next_block(txs(), &clock, &hasher);
+    block.header.created_at = Timestamp::UNIX_EPOCH;
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::TimestampNotAfterParent {
+            parent: Timestamp::UNIX_EPOCH,
+            found: Timestamp::UNIX_EPOCH
+        })
+    );
+
+    // a block from the future is accepted once the clock catches up
+    let mut block = blockchain.next_block(txs(), &clock, &hasher);
+    let maximum = clock.now().checked_add(MAX_FUTURE_DRIFT).unwrap();
+    block.header.created_at = maximum.checked_add(Duration::from_secs(1)).unwrap();
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::TimestampInFuture {
+            maximum,
+            found: maximum.checked_add(Duration::from_secs(1)).unwrap()
+        })
+    );
+    clock.advance(Duration::from_secs(1));
+    let mut block = blockchain.next_block(txs(), &clock, &hasher);
+    block.header.created_at = clock.now().checked_add(MAX_FUTURE_DRIFT).unwrap();
+    let hash = block.hash(&hasher);
+    blockchain.insert(block, &hasher, &clock).unwrap();
+
+    let block = blockchain.find_hash(hash).unwrap();
+    let duplicate = Block::from_bytes(&block.to_bytes()).unwrap();
+    assert_eq!(
+        blockchain.insert(duplicate, &hasher, &clock),
+        Err(Rejection::Duplicate(hash))
+    );
+}
+
+#[test]
+fn side_blocks() {
+    use std::time::Duration;
+    use zgc_common::MockClock;
+
+    let hasher = Sha256::new();
+    let mut blockchain = Blockchain::new(&hasher);
+    let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+    let alice = Wallet::new(String::from("alice")).unwrap();
+    let tx = alice
+        .new_transaction(
+            Amount::from_base_units(10).unwrap(),
+            Address::zero(),
+            String::from("alice"),
+        )
+        .unwrap();
+
+    let coinbase = TxData::coinbase(alice.address(), Amount::from_zgc(50).unwrap());
+    let first = blockchain.next_block(vec![coinbase, tx], &clock, &hasher);
+    let mut second = Block::from_bytes(&first.to_bytes()).unwrap();
+    second.header.created_at = clock.now().checked_add(Duration::from_secs(1)).unwrap();
+    let first_hash = first.hash(&hasher);
+    let second_hash = second.hash(&hasher);
+
+    blockchain.insert(first, &hasher, &clock).unwrap();
+    // the second block at the same height has the same work, so it doesn't
+    // replace the first one
+    assert_eq!(
+        blockchain.insert(second, &hasher, &clock),
+        Ok(Reorg::default())
+    );
+    assert_eq!(blockchain.last().unwrap().hash(&hasher), first_hash);
+    assert!(blockchain.find_hash(second_hash).is_some());
+}
+
+#[test]
+fn block_transactions() {
+    use zgc_common::MockClock;
+
+    let hasher = Sha256::new();
+    let params = Params {
+        max_block_transactions: 3,
+        max_block_size: 400,
+        ..Params::default()
+    };
+    let mut blockchain = Blockchain::with_params(params, &hasher);
+    let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+    let alice = Wallet::new(String::from("alice")).unwrap();
+    let tx = |amount| {
+        alice
+            .new_transaction(
+                Amount::from_base_units(amount).unwrap(),
+                Address::zero(),
+                String::from("alice"),
+            )
+            .unwrap()
+    };
+    let coinbase = TxData::coinbase(alice.address(), Amount::from_zgc(50).unwrap());
+    assert!(coinbase.is_coinbase());
+    assert!(!tx(1).is_coinbase());
+
+    let block = blockchain.next_block(vec![], &clock, &hasher);
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::MissingCoinbase)
+    );
+    let block = blockchain.next_block(vec![tx(1)], &clock, &hasher);
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::MissingCoinbase)
+    );
+
+    let mut invalid = coinbase.clone();
+    invalid.sender = alice.address().into();
+    let block = blockchain.next_block(vec![invalid], &clock, &hasher);
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::InvalidCoinbase)
+    );
+
+    let block = blockchain.next_block(vec![coinbase.clone(), coinbase.clone()], &clock, &hasher);
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::MisplacedCoinbase(1))
+    );
+
+    let transactions = vec![coinbase.clone(), tx(1), tx(2), tx(3)];
+    let block = blockchain.next_block(transactions, &clock, &hasher);
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::TooManyTransactions {
+            maximum: 3,
+            found: 4
         })
+    );
+
+    let block = blockchain.next_block(vec![coinbase.clone(), tx(1), tx(2)], &clock, &hasher);
+    let size = block.to_bytes().len();
+    assert!(size > 400);
+    assert_eq!(
+        blockchain.insert(block, &hasher, &clock),
+        Err(Rejection::TooLarge {
+            maximum: 400,
+            found: size
+        })
+    );
+
+    let block = blockchain.next_block(vec![coinbase, tx(1)], &clock, &hasher);
+    assert!(block.to_bytes().len() <= 400);
+    for (index, tx) in block.transactions().iter().enumerate() {
+        let proof = block.transaction_proof(index).unwrap();
+        assert!(proof.verify(&tx.hash(), &block.header.merkle_root));
     }
+    assert_eq!(block.transaction_proof(2), None);
+    blockchain.insert(block, &hasher, &clock).unwrap();
 }
 
-fn keygen(private_key: String) -> Address {
+#[test]
+fn longest_chain() {
+    use std::time::Duration;
+    use zgc_common::MockClock;
+
     let hasher = Sha256::new();
-    let hash_result = hasher.digest(private_key).to_string();
+    let mut blockchain = Blockchain::new(&hasher);
+    let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+    let coinbase = || vec![TxData::coinbase(Address::zero(), Amount::ZERO)];
+    let genesis = blockchain.last().unwrap().clone();
+
+    let a1 = blockchain.next_block(coinbase(), &clock, &hasher);
+    let a1_hash = a1.hash(&hasher);
+    blockchain.insert(a1, &hasher, &clock).unwrap();
+    assert_eq!(blockchain.chain_work(), U256::from(2_u64));
+
+    // a competing branch only becomes canonical once it is longer
+    clock.advance(Duration::from_secs(1));
+    let b1 = blockchain.block_on(&genesis, coinbase(), &clock, &hasher);
+    let b1_hash = b1.hash(&hasher);
+    assert_eq!(
+        blockchain.insert(b1.clone(), &hasher, &clock),
+        Ok(Reorg::default())
+    );
+    clock.advance(Duration::from_secs(1));
+    let b2 = blockchain.block_on(&b1, coinbase(), &clock, &hasher);
+    let b2_hash = b2.hash(&hasher);
+    assert_eq!(
+        blockchain.insert(b2, &hasher, &clock),
+        Ok(Reorg {
+            disconnected: vec![a1_hash],
+            connected: vec![b1_hash, b2_hash]
+        })
+    );
+    assert_eq!(blockchain.last().unwrap().hash(&hasher), b2_hash);
+    assert_eq!(blockchain.find_height(1).unwrap().hash(&hasher), b1_hash);
+    assert_eq!(blockchain.chain_work(), U256::from(3_u64));
+    // the old branch is still known and can be extended
+    assert!(blockchain.find_hash(a1_hash).is_some());
 
-    // uwrap is fine ...
-    Address::try_from_str(&hash_result[..40]).unwrap()
+    let a1 = blockchain.find_hash(a1_hash).unwrap().clone();
+    clock.advance(Duration::from_secs(1));
+    let a2 = blockchain.block_on(&a1, coinbase(), &clock, &hasher);
+    let a2_hash = a2.hash(&hasher);
+    blockchain.insert(a2.clone(), &hasher, &clock).unwrap();
+    clock.advance(Duration::from_secs(1));
+    let a3 = blockchain.block_on(&a2, coinbase(), &clock, &hasher);
+    let a3_hash = a3.hash(&hasher);
+    assert_eq!(
+        blockchain.insert(a3, &hasher, &clock),
+        Ok(Reorg {
+            disconnected: vec![b2_hash, b1_hash],
+            connected: vec![a1_hash, a2_hash, a3_hash]
+        })
+    );
+    assert_eq!(blockchain.find_height(3).unwrap().hash(&hasher), a3_hash);
 }
 
 #[test]
-fn test_keygen() {
-    let address = keygen(String::from("random2private#key"));
+fn most_work() {
+    use std::time::Duration;
+    use zgc_common::MockClock;
+
+    // every block may change the target, based on the solve time of its
+    // parent
+    let hasher = Sha256::new();
+    let params = Params {
+        pow_limit: Some(U256::MAX >> 1),
+        target_spacing: Duration::from_millis(100),
+        retarget: Retarget::Lwma { window: 1 },
+        ..Params::default()
+    };
+    let mut blockchain = Blockchain::with_params(params, &hasher);
+    let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+    let miner = Miner::new(Sha256::new());
+    let coinbase = || vec![TxData::coinbase(Address::zero(), Amount::ZERO)];
+    let mine = |parent: &Block, blockchain: &mut Blockchain| {
+        let block = blockchain.block_on(parent, coinbase(), &clock, &hasher);
+        let block = miner.mine(block).unwrap();
+        let reorg = blockchain.insert(block.clone(), &hasher, &clock).unwrap();
+        (block, reorg)
+    };
+
+    let (p1, _) = mine(&blockchain.last().unwrap().clone(), &mut blockchain);
+
+    // a branch of slow blocks stays at the limit
+    clock.advance(Duration::from_secs(1));
+    let (a1, _) = mine(&p1, &mut blockchain);
+    clock.advance(Duration::from_secs(1));
+    let (a2, _) = mine(&a1, &mut blockchain);
+    clock.advance(Duration::from_secs(1));
+    let (a3, _) = mine(&a2, &mut blockchain);
+    assert_eq!(blockchain.last(), Some(&a3));
+    assert_eq!(a3.target(), p1.target());
+
+    // a fast block makes the next one a lot harder, so a shorter branch
+    // has more work
+    clock.set(
+        p1.header
+            .created_at
+            .checked_add(Duration::from_millis(1))
+            .unwrap(),
+    );
+    let (b1, reorg) = mine(&p1, &mut blockchain);
+    assert!(reorg.is_empty());
+    clock.set(a3.header.created_at);
+    let (b2, reorg) = mine(&b1, &mut blockchain);
+    assert!(b2.target().work() > a2.target().work().saturating_mul(U256::from(2_u64)));
     assert_eq!(
-        address.to_string(),
-        "9ccf1c4cf49cb403f61aafff4c37a9b5a8f660cb"
+        reorg,
+        Reorg {
+            disconnected: vec![a3.hash(&hasher), a2.hash(&hasher), a1.hash(&hasher)],
+            connected: vec![b1.hash(&hasher), b2.hash(&hasher)]
+        }
     );
+    assert_eq!(blockchain.last(), Some(&b2));
+    assert_eq!(blockchain.find_height(4), None);
+}
+
+#[test]
+fn orphans() {
+    use std::time::Duration;
+    use zgc_common::MockClock;
+
+    let hasher = Sha256::new();
+    let mut blockchain = Blockchain::new(&hasher);
+    let mut source = Blockchain::new(&hasher);
+    let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+    let coinbase = || vec![TxData::coinbase(Address::zero(), Amount::ZERO)];
+
+    let mut blocks = Vec::new();
+    for _ in 0..3 {
+        clock.advance(Duration::from_secs(1));
+        let block = source.next_block(coinbase(), &clock, &hasher);
+        source.insert(block.clone(), &hasher, &clock).unwrap();
+        blocks.push(block);
+    }
+    let hashes: Vec<H256> = blocks.iter().map(|block| block.hash(&hasher)).collect();
+
+    // the blocks arrive in reverse order
+    assert_eq!(
+        blockchain.insert(blocks[2].clone(), &hasher, &clock),
+        Err(Rejection::UnknownParent(hashes[1]))
+    );
+    assert_eq!(blockchain.orphans().missing_parents(), vec![hashes[1]]);
+    assert_eq!(
+        blockchain.insert(blocks[1].clone(), &hasher, &clock),
+        Err(Rejection::UnknownParent(hashes[0]))
+    );
+    assert_eq!(blockchain.orphans().len(), 2);
+    assert_eq!(blockchain.orphans().missing_parents(), vec![hashes[0]]);
+
+    assert_eq!(
+        blockchain.insert(blocks[0].clone(), &hasher, &clock),
+        Ok(Reorg {
+            disconnected: vec![],
+            connected: hashes.clone()
+        })
+    );
+    assert!(blockchain.orphans().is_empty());
+    assert_eq!(blockchain.last(), source.last());
+
+    // an orphan that turns out to be invalid is dropped, a copy with a
+    // wrong height doesn't take the hash of the honest block
+    clock.advance(Duration::from_secs(1));
+    let parent = source.next_block(coinbase(), &clock, &hasher);
+    clock.advance(Duration::from_secs(1));
+    let child = source.block_on(&parent, coinbase(), &clock, &hasher);
+    let mut tampered = child.clone();
+    tampered.header.height = 10;
+    assert_ne!(tampered.hash(&hasher), child.hash(&hasher));
+    assert!(blockchain.insert(tampered, &hasher, &clock).is_err());
+    assert!(blockchain.insert(child.clone(), &hasher, &clock).is_err());
+    assert_eq!(blockchain.orphans().len(), 2);
+    assert_eq!(
+        blockchain.insert(parent.clone(), &hasher, &clock),
+        Ok(Reorg {
+            disconnected: vec![],
+            connected: vec![parent.hash(&hasher), child.hash(&hasher)]
+        })
+    );
+    assert!(blockchain.orphans().is_empty());
+
+    // the same holds for blocks with a known parent
+    clock.advance(Duration::from_secs(1));
+    let block = blockchain.next_block(coinbase(), &clock, &hasher);
+    let mut tampered = block.clone();
+    tampered.header.height = 1;
+    assert!(matches!(
+        blockchain.insert(tampered, &hasher, &clock),
+        Err(Rejection::InvalidHeight { .. })
+    ));
+    assert!(blockchain.insert(block, &hasher, &clock).is_ok());
+
+    // the pool limits are taken from the parameters
+    let params = Params {
+        max_orphans: 0,
+        ..Params::default()
+    };
+    let mut blockchain = Blockchain::with_params(params, &hasher);
+    assert!(blockchain
+        .insert(blocks[1].clone(), &hasher, &clock)
+        .is_err());
+    assert!(blockchain.orphans().is_empty());
 }
@@ -0,0 +1,254 @@
+// Merkle trees over transaction hashes.
+//
+// Leaves and inner nodes are hashed with different prefixes, so an inner
+// node can never be passed off as a leaf (second preimage attack). A node
+// without a sibling is moved up a level unchanged instead of being paired
+// with itself, so no two lists of leaves have the same root.
+//
+// An inclusion proof consists of the position of the leaf, the number of
+// leaves and the siblings on the path to the root. The number of leaves
+// tells the verifier on which levels the node has no sibling.
+
+use serde::{Deserialize, Serialize};
+use zgc_common::encoding::{Decode, Encode};
+use zgc_common::H256;
+use zgc_crypto::{Hasher, Sha256};
+
+const LEAF_PREFIX: u8 = 0x00;
+const INNER_PREFIX: u8 = 0x01;
+
+fn leaf_hash(leaf: &H256) -> H256 {
+    let mut bytes = [0_u8; 33];
+    bytes[0] = LEAF_PREFIX;
+    bytes[1..].copy_from_slice(leaf.as_bytes());
+    Sha256::new().digest_bytes(&bytes)
+}
+
+fn inner_hash(left: &H256, right: &H256) -> H256 {
+    let mut bytes = [0_u8; 65];
+    bytes[0] = INNER_PREFIX;
+    bytes[1..33].copy_from_slice(left.as_bytes());
+    bytes[33..].copy_from_slice(right.as_bytes());
+    Sha256::new().digest_bytes(&bytes)
+}
+
+/// Hashes one level of the tree into the next.
+fn next_level(level: &[H256]) -> Vec<H256> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => inner_hash(left, right),
+            [single] => *single,
+            _ => unreachable!("chunks of at most 2 nodes"),
+        })
+        .collect()
+}
+
+/// The root of the tree over `leaves`, zero if there are none.
+pub fn root(leaves: &[H256]) -> H256 {
+    MerkleTree::new(leaves).root()
+}
+
+/// A Merkle tree with all of its levels, for creating inclusion proofs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree {
+    /// The hashed leaves first, the root last.
+    levels: Vec<Vec<H256>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: &[H256]) -> Self {
+        let mut levels = vec![leaves.iter().map(leaf_hash).collect::<Vec<_>>()];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = next_level(&levels[levels.len() - 1]);
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// The root of the tree, zero if it has no leaves.
+    pub fn root(&self) -> H256 {
+        self.levels[self.levels.len() - 1]
+            .first()
+            .copied()
+            .unwrap_or_else(H256::zero)
+    }
+
+    /// The inclusion proof of the leaf at `index`.
+    pub fn proof(&self, index: usize) -> Option<Proof> {
+        if index >= self.leaf_count() {
+            return None;
+        }
+
+        let mut siblings = Vec::new();
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            // the last node of a level with an odd length has no sibling
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            position /= 2;
+        }
+
+        Some(Proof {
+            index,
+            leaf_count: self.leaf_count(),
+            siblings,
+        })
+    }
+}
+
+/// Proves that a leaf is at a position of the tree with a given root.
+#[derive(Serialize, Deserialize, Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    index: usize,
+    leaf_count: usize,
+    siblings: Vec<H256>,
+}
+
+impl Proof {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Returns true if `leaf` is at the position of the proof in the tree
+    /// with the given root.
+    pub fn verify(&self, leaf: &H256, root: &H256) -> bool {
+        if self.index >= self.leaf_count {
+            return false;
+        }
+
+        let mut siblings = self.siblings.iter();
+        let mut hash = leaf_hash(leaf);
+        let mut position = self.index;
+        let mut width = self.leaf_count;
+
+        while width > 1 {
+            if position % 2 == 1 {
+                match siblings.next() {
+                    Some(sibling) => hash = inner_hash(sibling, &hash),
+                    None => return false,
+                }
+            } else if position + 1 < width {
+                match siblings.next() {
+                    Some(sibling) => hash = inner_hash(&hash, sibling),
+                    None => return false,
+                }
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+
+        // every sibling has to be used
+        siblings.next().is_none() && hash == *root
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn leaves(count: u8) -> Vec<H256> {
+        (0..count).map(|i| H256::new([i; 32])).collect()
+    }
+
+    #[test]
+    fn shape() {
+        let l = leaves(3);
+        assert_eq!(root(&[]), H256::zero());
+        assert_eq!(root(&l[..1]), leaf_hash(&l[0]));
+        assert_eq!(
+            root(&l),
+            inner_hash(
+                &inner_hash(&leaf_hash(&l[0]), &leaf_hash(&l[1])),
+                &leaf_hash(&l[2])
+            )
+        );
+    }
+
+    #[test]
+    fn distinct_roots() {
+        let l = leaves(3);
+        // duplicating the odd leaf changes the root
+        let duplicated = [l[0], l[1], l[2], l[2]];
+        assert_ne!(root(&l), root(&duplicated));
+        // an inner node is not a valid leaf
+        let inner = inner_hash(&leaf_hash(&l[0]), &leaf_hash(&l[1]));
+        assert_ne!(root(&l[..2]), root(&[inner]));
+        assert_ne!(root(&l[..2]), root(&[l[1], l[0]]));
+    }
+
+    #[test]
+    fn proofs() {
+        for count in 1..=9 {
+            let l = leaves(count);
+            let tree = MerkleTree::new(&l);
+            assert_eq!(tree.root(), root(&l));
+
+            for (i, leaf) in l.iter().enumerate() {
+                let proof = tree.proof(i).unwrap();
+                assert!(proof.verify(leaf, &tree.root()));
+
+                // the proof is bound to the leaf, its position and the root
+                assert!(!proof.verify(&H256::new([0xff; 32]), &tree.root()));
+                assert!(!proof.verify(leaf, &H256::zero()));
+                let moved = Proof {
+                    index: (i + 1) % l.len(),
+                    ..proof.clone()
+                };
+                assert!(l.len() == 1 || !moved.verify(leaf, &tree.root()));
+            }
+            assert_eq!(tree.proof(l.len()), None);
+        }
+    }
+
+    #[test]
+    fn malformed_proofs() {
+        let l = leaves(5);
+        let tree = MerkleTree::new(&l);
+        let proof = tree.proof(4).unwrap();
+        // the last leaf of 5 is promoted twice and paired once
+        assert_eq!(proof.siblings.len(), 1);
+
+        let mut extended = proof.clone();
+        extended.siblings.push(H256::zero());
+        assert!(!extended.verify(&l[4], &tree.root()));
+
+        let mut truncated = tree.proof(0).unwrap();
+        truncated.siblings.pop();
+        assert!(!truncated.verify(&l[0], &tree.root()));
+
+        let out_of_range = Proof {
+            index: 5,
+            ..proof.clone()
+        };
+        assert!(!out_of_range.verify(&l[4], &tree.root()));
+
+        // a tree with a different number of leaves has a different shape
+        let resized = Proof {
+            leaf_count: 8,
+            ..proof
+        };
+        assert!(!resized.verify(&l[4], &tree.root()));
+    }
+
+    #[test]
+    fn serialization() {
+        let l = leaves(3);
+        let tree = MerkleTree::new(&l);
+        let proof = tree.proof(1).unwrap();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: Proof = serde_json::from_str(&json).unwrap();
+        assert!(decoded.verify(&l[1], &tree.root()));
+        assert_eq!(Proof::from_bytes(&proof.to_bytes()), Ok(proof));
+    }
+}
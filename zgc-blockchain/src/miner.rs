@@ -0,0 +1,420 @@
+// Proof-of-work mining.
+//
+// A miner searches for a nonce that makes the block hash meet the target of
+// the header. The search can be split into nonce ranges that are searched
+// on multiple threads, until one of them finds a solution or the search is
+// cancelled.
+
+use crate::Block;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use zgc_crypto::Hasher;
+
+/// The time between two progress reports of [`Miner::mine_parallel`].
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// The time between two checks of whether a solution was found or mining
+/// was cancelled.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// The number of hashes a worker computes before it adds them to the total
+/// and checks whether to stop.
+const HASH_BATCH: u64 = 1024;
+
+/// Sets the flag when dropped, so worker threads also stop when the
+/// thread waiting for them panics.
+pub(crate) struct StopOnDrop<'a>(pub(crate) &'a AtomicBool);
+
+impl Drop for StopOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Searches for a nonce that makes the hash of a block meet its target.
+///
+/// All values of the nonce are tried in order, starting with the one in the
+/// header. Once they are exhausted, the extra nonce is incremented and the
+/// nonce starts over at zero.
+pub struct Miner<H> {
+    hasher: H,
+}
+
+impl<H: Hasher> Miner<H> {
+    pub fn new(hasher: H) -> Self {
+        Self { hasher }
+    }
+
+    /// Mines `block`, returns `None` if its target is invalid or all nonces
+    /// and extra nonces are exhausted.
+    pub fn mine(&self, mut block: Block) -> Option<Block> {
+        let target = block.header.target;
+        target.to_target()?;
+
+        while !target.is_met_by(&block.hash(&self.hasher)) {
+            match block.header.nonce.checked_add(1) {
+                Some(nonce) => block.header.nonce = nonce,
+                None => {
+                    block.header.nonce = 0;
+                    block.header.extra_nonce = block.header.extra_nonce.checked_add(1)?;
+                }
+            }
+        }
+        Some(block)
+    }
+}
+
+/// A snapshot of a running parallel search.
+#[derive(Clone, Debug)]
+pub struct MiningProgress {
+    pub hashes: u64,
+    pub elapsed: Duration,
+}
+
+impl MiningProgress {
+    pub fn hashes_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.hashes as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+impl<H: Hasher + Sync> Miner<H> {
+    /// Mines `block` on `threads` worker threads, each searching its own
+    /// range of nonces. Once a worker has exhausted its range, it moves on
+    /// to the next extra nonce.
+    ///
+    /// `report` is called on the calling thread roughly every second with
+    /// the current progress. All workers stop as soon as one of them finds a
+    /// solution, or when `cancel` is set, e.g. because a new tip arrived, in
+    /// which case `None` is returned. `None` is also returned if the target
+    /// of the block is invalid.
+    pub fn mine_parallel(
+        &self,
+        block: Block,
+        threads: usize,
+        cancel: &AtomicBool,
+        mut report: impl FnMut(&MiningProgress),
+    ) -> Option<Block> {
+        block.header.target.to_target()?;
+
+        let hashes = AtomicU64::new(0);
+        let done = AtomicBool::new(false);
+        let found = Mutex::new(None);
+        let start = Instant::now();
+
+        thread::scope(|scope| {
+            for nonces in nonce_ranges(threads.max(1)) {
+                let block = block.clone();
+                let (hashes, done, found) = (&hashes, &done, &found);
+                scope.spawn(move || self.work(block, nonces, hashes, done, cancel, found));
+            }
+            // stops the workers when mining ends, also if `report` panics
+            let _stop = StopOnDrop(&done);
+
+            let mut last_report = start;
+            while !done.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+
+                if last_report.elapsed() >= REPORT_INTERVAL {
+                    last_report = Instant::now();
+                    report(&MiningProgress {
+                        hashes: hashes.load(Ordering::Relaxed),
+                        elapsed: start.elapsed(),
+                    });
+                }
+            }
+        });
+
+        // unwrap is fine because the workers don't panic while holding the lock
+        found.into_inner().unwrap()
+    }
+
+    fn work(
+        &self,
+        mut block: Block,
+        nonces: Range<u64>,
+        hashes: &AtomicU64,
+        done: &AtomicBool,
+        cancel: &AtomicBool,
+        found: &Mutex<Option<Block>>,
+    ) {
+        let target = block.header.target;
+        let mut batch = 0;
+        loop {
+            for nonce in nonces.clone() {
+                // the ranges end at 2^32
+                block.header.nonce = nonce as u32;
+                batch += 1;
+
+                if target.is_met_by(&block.hash(&self.hasher)) {
+                    hashes.fetch_add(batch, Ordering::Relaxed);
+                    let mut found = found.lock().unwrap();
+                    if found.is_none() {
+                        *found = Some(block);
+                    }
+                    done.store(true, Ordering::Relaxed);
+                    return;
+                }
+
+                if batch == HASH_BATCH {
+                    hashes.fetch_add(batch, Ordering::Relaxed);
+                    batch = 0;
+                    if done.load(Ordering::Relaxed) || cancel.load(Ordering::Relaxed) {
+                        return;
+                    }
+                }
+            }
+
+            match block.header.extra_nonce.checked_add(1) {
+                Some(extra_nonce) => block.header.extra_nonce = extra_nonce,
+                None => return,
+            }
+        }
+    }
+}
+
+/// Splits the nonces into `threads` contiguous ranges of about the same
+/// size.
+fn nonce_ranges(threads: usize) -> Vec<Range<u64>> {
+    let nonces = u64::from(u32::MAX) + 1;
+    let threads = threads as u64;
+    (0..threads)
+        .map(|i| nonces * i / threads..nonces * (i + 1) / threads)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Blockchain, CompactTarget, Params, Rejection, TxData};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use zgc_common::encoding::{Decode, Encode};
+    use zgc_common::{Address, Amount, MockClock, Timestamp, U256};
+    use zgc_crypto::Sha256;
+
+    fn blockchain() -> Blockchain {
+        let params = Params {
+            pow_limit: Some(U256::MAX >> 8),
+            ..Params::default()
+        };
+        Blockchain::with_params(params, &Sha256::new())
+    }
+
+    fn coinbase() -> Vec<TxData> {
+        vec![TxData::coinbase(
+            Address::zero(),
+            Amount::from_zgc(50).unwrap(),
+        )]
+    }
+
+    #[test]
+    fn mining() {
+        let hasher = Sha256::new();
+        let mut blockchain = blockchain();
+        let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+        let miner = Miner::new(Sha256::new());
+
+        let block = blockchain.next_block(coinbase(), &clock, &hasher);
+        assert_eq!(block.target(), CompactTarget::from_target(U256::MAX >> 8));
+        let block = miner.mine(block).unwrap();
+        assert!(block.target().is_met_by(&block.hash(&hasher)));
+        blockchain.insert(block, &hasher, &clock).unwrap();
+
+        let mut invalid = blockchain.next_block(coinbase(), &clock, &hasher);
+        invalid.header.target = CompactTarget::default();
+        assert_eq!(miner.mine(invalid), None);
+    }
+
+    #[test]
+    fn extra_nonce() {
+        let hasher = Sha256::new();
+        let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+        let miner = Miner::new(Sha256::new());
+
+        // find a block whose last nonce doesn't meet the target
+        let mut block = blockchain().next_block(coinbase(), &clock, &hasher);
+        block.header.nonce = u32::MAX;
+        while block.target().is_met_by(&block.hash(&hasher)) {
+            block.header.extra_nonce += 1;
+        }
+        let extra_nonce = block.header.extra_nonce;
+
+        let block = miner.mine(block).unwrap();
+        assert_eq!(block.header.extra_nonce, extra_nonce + 1);
+        assert!(block.target().is_met_by(&block.hash(&hasher)));
+    }
+
+    #[test]
+    fn proof_of_work() {
+        let hasher = Sha256::new();
+        let mut blockchain = blockchain();
+        let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+        let miner = Miner::new(Sha256::new());
+
+        // a block whose hash is above its target
+        let mut block = blockchain.next_block(coinbase(), &clock, &hasher);
+        while block.target().is_met_by(&block.hash(&hasher)) {
+            block.header.nonce += 1;
+        }
+        let hash = block.hash(&hasher);
+        assert_eq!(
+            blockchain.insert(block, &hasher, &clock),
+            Err(Rejection::InsufficientWork {
+                target: CompactTarget::from_target(U256::MAX >> 8),
+                hash
+            })
+        );
+
+        // the target may not be easier than the limit
+        let mut block = blockchain.next_block(coinbase(), &clock, &hasher);
+        block.header.target = CompactTarget::from_target(U256::MAX >> 7);
+        let block = miner.mine(block).unwrap();
+        assert_eq!(
+            blockchain.insert(block, &hasher, &clock),
+            Err(Rejection::InvalidTarget(CompactTarget::from_target(
+                U256::MAX >> 7
+            )))
+        );
+        let mut block = blockchain.next_block(coinbase(), &clock, &hasher);
+        block.header.target = CompactTarget::from_bits(0x0480_0000);
+        assert_eq!(
+            blockchain.insert(block, &hasher, &clock),
+            Err(Rejection::InvalidTarget(CompactTarget::from_bits(
+                0x0480_0000
+            )))
+        );
+
+        // nor differ from the target computed from the ancestors
+        let mut block = blockchain.next_block(coinbase(), &clock, &hasher);
+        block.header.target = CompactTarget::from_target(U256::MAX >> 12);
+        let block = miner.mine(block).unwrap();
+        assert_eq!(
+            blockchain.insert(block, &hasher, &clock),
+            Err(Rejection::UnexpectedTarget {
+                expected: CompactTarget::from_target(U256::MAX >> 8),
+                found: CompactTarget::from_target(U256::MAX >> 12)
+            })
+        );
+    }
+
+    #[test]
+    fn retargeting() {
+        use crate::Retarget;
+
+        let hasher = Sha256::new();
+        let params = Params {
+            pow_limit: Some(U256::MAX >> 8),
+            target_spacing: Duration::from_secs(10),
+            retarget: Retarget::Periodic { interval: 4 },
+            ..Params::default()
+        };
+        let mut blockchain = Blockchain::with_params(params, &hasher);
+        let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+        let miner = Miner::new(Sha256::new());
+        let limit = CompactTarget::from_target(U256::MAX >> 8);
+
+        // blocks arrive twice as fast as intended, but the first interval
+        // starts with the genesis block at the epoch, so the target stays at
+        // the limit
+        for _ in 1..8 {
+            clock.advance(Duration::from_secs(5));
+            let block = blockchain.next_block(coinbase(), &clock, &hasher);
+            assert_eq!(block.target(), limit);
+            let block = miner.mine(block).unwrap();
+            blockchain.insert(block, &hasher, &clock).unwrap();
+        }
+
+        // the second interval halves the target
+        clock.advance(Duration::from_secs(5));
+        let halved = CompactTarget::from_target(limit.to_target().unwrap() >> 1);
+        let block = blockchain.next_block(coinbase(), &clock, &hasher);
+        assert_eq!(block.target(), halved);
+
+        let mut stale = Block::from_bytes(&block.to_bytes()).unwrap();
+        stale.header.target = limit;
+        let stale = miner.mine(stale).unwrap();
+        assert_eq!(
+            blockchain.insert(stale, &hasher, &clock),
+            Err(Rejection::UnexpectedTarget {
+                expected: halved,
+                found: limit
+            })
+        );
+        let block = miner.mine(block).unwrap();
+        blockchain.insert(block, &hasher, &clock).unwrap();
+    }
+
+    #[test]
+    fn nonce_partition() {
+        let ranges = nonce_ranges(3);
+        assert_eq!(ranges[0].start, 0);
+        assert_eq!(ranges[1].start, ranges[0].end);
+        assert_eq!(ranges[2].start, ranges[1].end);
+        assert_eq!(ranges[2].end, 1 << 32);
+        assert_eq!(nonce_ranges(1), vec![0..1 << 32]);
+    }
+
+    #[test]
+    fn parallel_mining() {
+        let hasher = Sha256::new();
+        let params = Params {
+            pow_limit: Some(U256::MAX >> 12),
+            ..Params::default()
+        };
+        let mut blockchain = Blockchain::with_params(params, &hasher);
+        let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+        let miner = Miner::new(Sha256::new());
+        let cancel = AtomicBool::new(false);
+
+        let block = blockchain.next_block(coinbase(), &clock, &hasher);
+        let block = miner.mine_parallel(block, 4, &cancel, |_| {}).unwrap();
+        assert!(block.target().is_met_by(&block.hash(&hasher)));
+        blockchain.insert(block, &hasher, &clock).unwrap();
+
+        let mut invalid = blockchain.next_block(coinbase(), &clock, &hasher);
+        invalid.header.target = CompactTarget::default();
+        assert_eq!(miner.mine_parallel(invalid, 4, &cancel, |_| {}), None);
+    }
+
+    #[test]
+    fn parallel_mining_cancel() {
+        let hasher = Sha256::new();
+        let clock = MockClock::new(Timestamp::from_unix_secs(1_600_000_000).unwrap());
+        let miner = Miner::new(Sha256::new());
+        let cancel = AtomicBool::new(false);
+
+        // practically impossible to find
+        let mut block = blockchain().next_block(coinbase(), &clock, &hasher);
+        block.header.target = CompactTarget::from_target(U256::ONE);
+
+        let mut reports = 0;
+        let result = miner.mine_parallel(block, 2, &cancel, |progress| {
+            assert!(progress.hashes > 0);
+            assert!(progress.hashes_per_second() > 0.0);
+            reports += 1;
+            // a new tip arrived
+            cancel.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(result, None);
+        assert_eq!(reports, 1);
+    }
+
+    #[test]
+    fn progress() {
+        let progress = MiningProgress {
+            hashes: 3000,
+            elapsed: Duration::from_secs(2),
+        };
+        assert_eq!(progress.hashes_per_second(), 1500.0);
+        let progress = MiningProgress {
+            hashes: 0,
+            elapsed: Duration::from_secs(0),
+        };
+        assert_eq!(progress.hashes_per_second(), 0.0);
+    }
+}
@@ -0,0 +1,230 @@
+// Blocks that arrived before their parent.
+//
+// A [`crate::Blockchain`] keeps such blocks in an orphan pool, keyed by the
+// hash of the missing parent, and connects them once the parent is
+// inserted. The pool is bounded in size and age, because orphans cannot be
+// fully validated and anybody can send them.
+
+use crate::Block;
+use std::collections::HashMap;
+use std::time::Duration;
+use zgc_common::{Timestamp, H256};
+
+/// The default maximal number of blocks in an [`OrphanPool`].
+pub const MAX_ORPHANS: usize = 100;
+/// The default time after which a block is evicted from an [`OrphanPool`].
+pub const MAX_ORPHAN_AGE: Duration = Duration::from_secs(20 * 60);
+
+#[derive(Debug)]
+struct Orphan {
+    block: Block,
+    received_at: Timestamp,
+    /// Orders orphans received at the same time.
+    sequence: u64,
+}
+
+/// A bounded pool of blocks whose parent is unknown.
+#[derive(Debug)]
+pub struct OrphanPool {
+    max_blocks: usize,
+    max_age: Duration,
+    orphans: HashMap<H256, Orphan>,
+    /// The orphans waiting for a parent.
+    children: HashMap<H256, Vec<H256>>,
+    sequence: u64,
+}
+
+impl OrphanPool {
+    pub fn new(max_blocks: usize, max_age: Duration) -> Self {
+        Self {
+            max_blocks,
+            max_age,
+            orphans: HashMap::new(),
+            children: HashMap::new(),
+            sequence: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.orphans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orphans.is_empty()
+    }
+
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.orphans.contains_key(hash)
+    }
+
+    /// The hashes of the blocks that have to be requested to connect the
+    /// orphans, in ascending order. Parents that are orphans themselves are
+    /// not included, their missing ancestors are.
+    pub fn missing_parents(&self) -> Vec<H256> {
+        let mut parents: Vec<H256> = self
+            .children
+            .keys()
+            .filter(|parent| !self.orphans.contains_key(parent))
+            .copied()
+            .collect();
+        parents.sort();
+        parents
+    }
+
+    /// Adds a block whose parent is unknown, after evicting expired blocks
+    /// and, if the pool is full, the oldest block.
+    pub(crate) fn insert(&mut self, hash: H256, block: Block, now: Timestamp) {
+        if self.max_blocks == 0 || self.contains(&hash) {
+            return;
+        }
+
+        self.evict_expired(now);
+        if self.orphans.len() >= self.max_blocks {
+            let oldest = self
+                .orphans
+                .iter()
+                .min_by_key(|(_, orphan)| (orphan.received_at, orphan.sequence))
+                .map(|(hash, _)| *hash);
+            if let Some(oldest) = oldest {
+                self.remove(&oldest);
+            }
+        }
+
+        self.children
+            .entry(block.header.previous_hash)
+            .or_default()
+            .push(hash);
+        self.orphans.insert(
+            hash,
+            Orphan {
+                block,
+                received_at: now,
+                sequence: self.sequence,
+            },
+        );
+        self.sequence += 1;
+    }
+
+    /// Removes the blocks received more than the maximal age before `now`.
+    pub(crate) fn evict_expired(&mut self, now: Timestamp) {
+        let expired: Vec<H256> = self
+            .orphans
+            .iter()
+            .filter(|(_, orphan)| {
+                now.duration_since(orphan.received_at)
+                    .is_some_and(|age| age > self.max_age)
+            })
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in &expired {
+            self.remove(hash);
+        }
+    }
+
+    /// Removes and returns the orphans waiting for `parent`, oldest first.
+    pub(crate) fn take_children(&mut self, parent: &H256) -> Vec<Block> {
+        let hashes = self.children.remove(parent).unwrap_or_default();
+        hashes
+            .iter()
+            .filter_map(|hash| self.orphans.remove(hash))
+            .map(|orphan| orphan.block)
+            .collect()
+    }
+
+    fn remove(&mut self, hash: &H256) {
+        let orphan = match self.orphans.remove(hash) {
+            Some(orphan) => orphan,
+            None => return,
+        };
+        let parent = orphan.block.header.previous_hash;
+        if let Some(siblings) = self.children.get_mut(&parent) {
+            siblings.retain(|sibling| sibling != hash);
+            if siblings.is_empty() {
+                self.children.remove(&parent);
+            }
+        }
+    }
+}
+
+impl Default for OrphanPool {
+    fn default() -> Self {
+        Self::new(MAX_ORPHANS, MAX_ORPHAN_AGE)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use zgc_crypto::Sha256;
+
+    fn block(parent: H256, nonce: u32) -> (H256, Block) {
+        let mut block = Block::default();
+        block.header.previous_hash = parent;
+        block.header.nonce = nonce;
+        (block.hash(&Sha256::new()), block)
+    }
+
+    fn at(secs: u64) -> Timestamp {
+        Timestamp::from_unix_secs(secs).unwrap()
+    }
+
+    #[test]
+    fn children() {
+        let mut pool = OrphanPool::default();
+        let parent = H256::new([1; 32]);
+        let (a, block_a) = block(parent, 0);
+        let (b, block_b) = block(parent, 1);
+        let (c, block_c) = block(a, 2);
+
+        pool.insert(a, block_a, at(0));
+        pool.insert(b, block_b, at(1));
+        pool.insert(c, block_c, at(2));
+        let (_, duplicate) = block(parent, 0);
+        pool.insert(a, duplicate, at(3));
+        assert_eq!(pool.len(), 3);
+        // c waits for a, which is an orphan itself
+        assert_eq!(pool.missing_parents(), vec![parent]);
+
+        let children = pool.take_children(&parent);
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].hash(&Sha256::new()), a);
+        assert_eq!(children[1].hash(&Sha256::new()), b);
+        assert_eq!(pool.missing_parents(), vec![a]);
+        assert!(pool.contains(&c));
+        assert_eq!(pool.take_children(&parent), vec![]);
+    }
+
+    #[test]
+    fn eviction() {
+        let mut pool = OrphanPool::new(2, Duration::from_secs(60));
+        let (a, block_a) = block(H256::new([1; 32]), 0);
+        let (b, block_b) = block(H256::new([2; 32]), 0);
+        let (c, block_c) = block(H256::new([3; 32]), 0);
+
+        // the oldest block is evicted when the pool is full
+        pool.insert(a, block_a, at(0));
+        pool.insert(b, block_b, at(0));
+        pool.insert(c, block_c, at(10));
+        assert!(!pool.contains(&a));
+        assert!(pool.contains(&b) && pool.contains(&c));
+        assert_eq!(
+            pool.missing_parents(),
+            vec![H256::new([2; 32]), H256::new([3; 32])]
+        );
+
+        // and blocks older than the maximal age
+        pool.evict_expired(at(60));
+        assert_eq!(pool.len(), 2);
+        pool.evict_expired(at(61));
+        assert_eq!(pool.len(), 1);
+        assert!(pool.contains(&c));
+        pool.evict_expired(at(71));
+        assert!(pool.is_empty());
+        assert_eq!(pool.missing_parents(), vec![]);
+
+        let mut disabled = OrphanPool::new(0, Duration::from_secs(60));
+        let (a, block_a) = block(H256::new([1; 32]), 0);
+        disabled.insert(a, block_a, at(0));
+        assert!(disabled.is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{CompactTarget, Retarget, MAX_ORPHANS, MAX_ORPHAN_AGE};
+use std::time::Duration;
+use zgc_common::U256;
+
+/// Consensus parameters of a [`crate::Blockchain`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Params {
+    /// Maximal number of transactions of a block, including the coinbase.
+    pub max_block_transactions: usize,
+    /// Maximal size of an encoded block in bytes.
+    pub max_block_size: usize,
+    /// The easiest target a block may declare, or `None` to accept blocks
+    /// without proof of work.
+    pub pow_limit: Option<U256>,
+    /// The intended average time between blocks.
+    pub target_spacing: Duration,
+    /// How the target follows changes of the hashrate.
+    pub retarget: Retarget,
+    /// Maximal number of blocks kept while their parent is unknown, 0
+    /// disables the orphan pool.
+    pub max_orphans: usize,
+    /// The time after which a block with an unknown parent is dropped.
+    pub max_orphan_age: Duration,
+}
+
+impl Params {
+    /// The compact form of the proof of work limit, the target of the
+    /// genesis block.
+    pub fn limit_target(&self) -> CompactTarget {
+        CompactTarget::from_target(self.pow_limit.unwrap_or(U256::MAX))
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            max_block_transactions: 1000,
+            max_block_size: 1_000_000,
+            pow_limit: None,
+            target_spacing: Duration::from_secs(10 * 60),
+            retarget: Retarget::Periodic { interval: 2016 },
+            max_orphans: MAX_ORPHANS,
+            max_orphan_age: MAX_ORPHAN_AGE,
+        }
+    }
+}
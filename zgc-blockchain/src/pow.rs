@@ -0,0 +1,170 @@
+// Proof of work.
+//
+// A block header declares a target in the compact format of Bitcoin's
+// `nBits`: the most significant byte is the length of the target in bytes,
+// the other three bytes are its most significant bytes. A block meets its
+// target if its hash, read as a big endian number, is not above the target.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zgc_common::encoding::{Decode, Encode};
+use zgc_common::{H256, U256};
+
+/// Set in the mantissa of a compact target, it would make the target
+/// negative in Bitcoin's format, so such targets are invalid.
+const SIGN_BIT: u32 = 0x0080_0000;
+
+/// A target in the compact 32 bit format.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Copy, Clone, Default, Debug, PartialEq, Eq, Hash,
+)]
+pub struct CompactTarget(u32);
+
+impl CompactTarget {
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// The compact form of `target`, rounded down to the precision of the
+    /// format.
+    pub fn from_target(target: U256) -> Self {
+        let mut size = target.bits().div_ceil(8);
+        let mut mantissa = if size <= 3 {
+            target.low_u64() << (8 * (3 - size))
+        } else {
+            (target >> (8 * (size - 3))).low_u64()
+        } as u32;
+        if mantissa & SIGN_BIT != 0 {
+            mantissa >>= 8;
+            size += 1;
+        }
+        Self(size << 24 | mantissa)
+    }
+
+    /// The expanded target, or `None` if it is zero, negative or larger
+    /// than 256 bits.
+    pub fn to_target(self) -> Option<U256> {
+        if self.0 & SIGN_BIT != 0 {
+            return None;
+        }
+
+        let size = self.0 >> 24;
+        let mantissa = U256::from(u64::from(self.0 & 0x007f_ffff));
+        let target = if size <= 3 {
+            mantissa >> (8 * (3 - size))
+        } else {
+            let shift = 8 * (size - 3);
+            if mantissa.bits() + shift > U256::BITS {
+                return None;
+            }
+            mantissa << shift
+        };
+        if target.is_zero() {
+            None
+        } else {
+            Some(target)
+        }
+    }
+
+    /// The expected number of hashes needed to meet the target, i.e.
+    /// `2^256 / (target + 1)`, or zero for invalid targets.
+    pub fn work(self) -> U256 {
+        match self.to_target() {
+            // 2^256 doesn't fit, but (2^256 - target - 1) / (target + 1) + 1
+            // is the same
+            Some(target) => match target.checked_add(U256::ONE) {
+                Some(divisor) => !target / divisor + U256::ONE,
+                None => U256::ONE,
+            },
+            None => U256::ZERO,
+        }
+    }
+
+    /// Returns true if `hash` meets the target. Invalid targets are never
+    /// met.
+    pub fn is_met_by(self, hash: &H256) -> bool {
+        match self.to_target() {
+            Some(target) => U256::from(*hash) <= target,
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for CompactTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:08x}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compact_format() {
+        // the difficulty 1 target of Bitcoin
+        let target = CompactTarget::from_bits(0x1d00_ffff);
+        assert_eq!(target.to_target(), Some(U256::from(0xffff_u64) << 208));
+        assert_eq!(
+            CompactTarget::from_target(U256::from(0xffff_u64) << 208),
+            target
+        );
+
+        // precision beyond three bytes is rounded down
+        let rounded = CompactTarget::from_target((U256::from(0x1234_5678_u64) << 104) | U256::ONE);
+        assert_eq!(rounded.bits(), 0x1112_3456);
+        assert_eq!(rounded.to_target(), Some(U256::from(0x12_3456_u64) << 112));
+
+        // a mantissa with the sign bit set is moved one byte down
+        assert_eq!(
+            CompactTarget::from_target(U256::from(0x80_u64)).bits(),
+            0x0200_8000
+        );
+        assert_eq!(CompactTarget::from_target(U256::MAX).bits(), 0x2100_ffff);
+        assert_eq!(CompactTarget::from_target(U256::ONE).bits(), 0x0101_0000);
+
+        for bits in [1_u32, 0xffff, 0x20ff_ffff] {
+            let target = U256::from(u64::from(bits)) << 100;
+            let compact = CompactTarget::from_target(target);
+            assert!(compact.to_target().unwrap() <= target);
+        }
+    }
+
+    #[test]
+    fn invalid_targets() {
+        assert_eq!(CompactTarget::default().to_target(), None);
+        assert_eq!(CompactTarget::from_bits(0x0100_00ff).to_target(), None);
+        assert_eq!(CompactTarget::from_bits(0x0480_0000).to_target(), None);
+        assert_eq!(CompactTarget::from_bits(0x2101_0000).to_target(), None);
+        assert!(!CompactTarget::default().is_met_by(&H256::zero()));
+        assert_eq!(CompactTarget::default().work(), U256::ZERO);
+    }
+
+    #[test]
+    fn work() {
+        // the difficulty 1 target of Bitcoin takes about 2^32 hashes
+        let target = CompactTarget::from_bits(0x1d00_ffff);
+        assert_eq!(target.work(), U256::from(0x1_0001_0001_u64));
+        assert_eq!(
+            CompactTarget::from_target(U256::ONE << 254).work(),
+            U256::from(3_u64)
+        );
+        assert_eq!(CompactTarget::from_target(U256::MAX).work(), U256::ONE);
+    }
+
+    #[test]
+    fn meeting_targets() {
+        let target = CompactTarget::from_target(U256::from(0xffff_u64) << 200);
+        assert!(target.is_met_by(&H256::zero()));
+        assert!(target.is_met_by(&H256::from(U256::from(0xffff_u64) << 200)));
+        assert!(!target.is_met_by(&H256::from((U256::from(0xffff_u64) << 200) + U256::ONE)));
+        assert!(!target.is_met_by(&H256::new([0xff; 32])));
+
+        assert_eq!(target.to_string(), "0x1c00ffff");
+        assert_eq!(target.to_bytes(), target.bits().to_le_bytes());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::Error;
+
+use rand::Rng;
+use std::convert::TryFrom;
+use zgc_common::{Network, H256};
+use zgc_crypto::{base58check, Hasher, Sha256};
+
+const MAINNET_VERSION: u8 = 0x80;
+const TESTNET_VERSION: u8 = 0xef;
+/// Marks that the public key of the private key is used in compressed form.
+const COMPRESSED_FLAG: u8 = 0x01;
+
+/// Secret key of a wallet.
+///
+/// It is either derived from a private key phrase or imported from the
+/// Wallet Import Format (WIF): the Base58Check encoding of the secret with a
+/// network version byte and a trailing compression flag.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PrivateKey(H256);
+
+impl PrivateKey {
+    /// Generates a random private key.
+    pub fn random() -> Self {
+        let mut rng = rand::thread_rng();
+        loop {
+            let secret = H256::new(rng.gen());
+            if zgc_crypto::is_valid_private_key(&secret) {
+                return Self(secret);
+            }
+        }
+    }
+
+    pub fn to_wif(&self, network: Network) -> String {
+        let mut payload = self.0.as_bytes().to_vec();
+        payload.push(COMPRESSED_FLAG);
+
+        base58check::encode(version(network), &payload)
+    }
+
+    pub fn from_wif(wif: &str, network: Network) -> Result<Self, Error> {
+        let (version, payload) = base58check::decode(wif)?;
+
+        let found = match version {
+            MAINNET_VERSION => Network::Mainnet,
+            TESTNET_VERSION => Network::Testnet,
+            _ => return Err(Error::UnknownPrivateKeyVersion(version)),
+        };
+        if found != network {
+            return Err(zgc_common::Error::WrongNetwork {
+                expected: network,
+                found,
+            }
+            .into());
+        }
+
+        match payload.len() {
+            33 if payload[32] == COMPRESSED_FLAG => {}
+            33 => return Err(Error::InvalidCompressionFlag(payload[32])),
+            32 => return Err(Error::UncompressedPrivateKey),
+            len => {
+                return Err(zgc_common::Error::InvalidLength {
+                    expected: 33,
+                    found: len,
+                }
+                .into())
+            }
+        }
+
+        let secret = H256::try_from(&payload[..32])?;
+        // rejects secrets that are not valid secp256k1 scalars
+        zgc_crypto::derive_public_key(&secret)?;
+
+        Ok(Self(secret))
+    }
+
+    pub(crate) fn secret(&self) -> &H256 {
+        &self.0
+    }
+}
+
+impl From<String> for PrivateKey {
+    fn from(phrase: String) -> Self {
+        let hasher = Sha256::new();
+        Self(hasher.digest(phrase))
+    }
+}
+
+// the secret is never printed
+impl std::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "PrivateKey(..)")
+    }
+}
+
+fn version(network: Network) -> u8 {
+    match network {
+        Network::Mainnet => MAINNET_VERSION,
+        Network::Testnet => TESTNET_VERSION,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SECRET: H256 =
+        zgc_common::hash!("0c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d");
+
+    #[test]
+    fn random() {
+        let private_key = PrivateKey::random();
+        assert_ne!(private_key, PrivateKey::random());
+
+        let wif = private_key.to_wif(Network::Mainnet);
+        assert_eq!(
+            PrivateKey::from_wif(&wif, Network::Mainnet),
+            Ok(private_key)
+        );
+    }
+
+    #[test]
+    fn wif_export() {
+        let private_key = PrivateKey(SECRET);
+        assert_eq!(
+            private_key.to_wif(Network::Mainnet),
+            "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617"
+        );
+        assert_eq!(
+            private_key.to_wif(Network::Testnet),
+            "cMzLdeGd5vEqxB8B6VFQoRopQ3sLAAvEzDAoQgvX54xwofSWj1fx"
+        );
+    }
+
+    #[test]
+    fn wif_import() {
+        let private_key = PrivateKey(SECRET);
+        assert_eq!(
+            PrivateKey::from_wif(
+                "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617",
+                Network::Mainnet
+            ),
+            Ok(private_key)
+        );
+        assert_eq!(
+            PrivateKey::from_wif(
+                "cMzLdeGd5vEqxB8B6VFQoRopQ3sLAAvEzDAoQgvX54xwofSWj1fx",
+                Network::Testnet
+            ),
+            Ok(private_key)
+        );
+
+        let private_key = PrivateKey::from(String::from("random2private#key"));
+        let wif = private_key.to_wif(Network::Testnet);
+        assert_eq!(
+            PrivateKey::from_wif(&wif, Network::Testnet),
+            Ok(private_key)
+        );
+    }
+
+    #[test]
+    fn invalid_wif() {
+        // mistyped character
+        assert_eq!(
+            PrivateKey::from_wif(
+                "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98618",
+                Network::Mainnet
+            ),
+            Err(Error::Crypto(zgc_crypto::Error::ChecksumMismatch {
+                expected: [0xa6, 0x20, 0x19, 0xd2],
+                found: [0xa6, 0x20, 0x19, 0xd3]
+            }))
+        );
+        assert_eq!(
+            PrivateKey::from_wif(
+                "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP9861l",
+                Network::Mainnet
+            ),
+            Err(Error::Common(zgc_common::Error::InvalidCharacter {
+                alphabet: zgc_common::Alphabet::Base58,
+                character: 'l',
+                position: 51
+            }))
+        );
+        assert_eq!(
+            PrivateKey::from_wif(
+                "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617",
+                Network::Testnet
+            ),
+            Err(Error::Common(zgc_common::Error::WrongNetwork {
+                expected: Network::Testnet,
+                found: Network::Mainnet
+            }))
+        );
+        assert_eq!(
+            PrivateKey::from_wif(
+                "3fqzGydpPwmTbpCQsk23rxuBQ2UAfgzb1aVzkJMbZKQeaWSDY3vg",
+                Network::Mainnet
+            ),
+            Err(Error::UnknownPrivateKeyVersion(0x12))
+        );
+        assert_eq!(
+            PrivateKey::from_wif(
+                "5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ",
+                Network::Mainnet
+            ),
+            Err(Error::UncompressedPrivateKey)
+        );
+        assert_eq!(
+            PrivateKey::from_wif(
+                "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvWxyf5d",
+                Network::Mainnet
+            ),
+            Err(Error::InvalidCompressionFlag(0x02))
+        );
+        assert_eq!(
+            PrivateKey::from_wif(
+                "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73Nd2Mcv1",
+                Network::Mainnet
+            ),
+            Err(Error::Crypto(zgc_crypto::Error::InvalidPrivateKey))
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{CompactTarget, Error};
+use std::fmt;
+use std::time::Duration;
+use zgc_common::{Timestamp, H256};
+
+/// How far the timestamp of a block may be ahead of the local clock.
+pub const MAX_FUTURE_DRIFT: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// The reason a block was not inserted into a [`crate::Blockchain`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    /// The block is already stored.
+    Duplicate(H256),
+    /// The declared target cannot be expanded or is easier than the limit
+    /// of the chain.
+    InvalidTarget(CompactTarget),
+    /// The block hash is above the declared target.
+    InsufficientWork { target: CompactTarget, hash: H256 },
+    /// The declared target is not the one computed from the ancestors.
+    UnexpectedTarget {
+        expected: CompactTarget,
+        found: CompactTarget,
+    },
+    /// The block's `previous_hash` is not a stored block.
+    UnknownParent(H256),
+    /// The height is not the height of the parent plus one.
+    InvalidHeight { expected: usize, found: usize },
+    /// The block is not newer than its parent.
+    TimestampNotAfterParent { parent: Timestamp, found: Timestamp },
+    /// The block is more than [`MAX_FUTURE_DRIFT`] ahead of the local clock.
+    TimestampInFuture {
+        maximum: Timestamp,
+        found: Timestamp,
+    },
+    /// The header doesn't commit to the block's transactions.
+    InvalidMerkleRoot { expected: H256, found: H256 },
+    /// The block has more transactions than the maximum.
+    TooManyTransactions { maximum: usize, found: usize },
+    /// The encoded block is larger than the maximum size in bytes.
+    TooLarge { maximum: usize, found: usize },
+    /// The block has no transactions or the first one is not a coinbase.
+    MissingCoinbase,
+    /// The coinbase has a sender or signatures.
+    InvalidCoinbase,
+    /// A coinbase transaction at an index other than 0.
+    MisplacedCoinbase(usize),
+    /// The transaction at `index` failed validation.
+    InvalidTransaction { index: usize, source: Error },
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Duplicate(hash) => write!(f, "block {} is already known", hash),
+            Self::InvalidTarget(target) => write!(f, "block target {} is invalid", target),
+            Self::InsufficientWork { target, hash } => {
+                write!(f, "block hash {} does not meet the target {}", hash, target)
+            }
+            Self::UnexpectedTarget { expected, found } => {
+                write!(f, "block target is {}, expected {}", found, expected)
+            }
+            Self::UnknownParent(hash) => write!(f, "parent block {} is unknown", hash),
+            Self::InvalidHeight { expected, found } => {
+                write!(f, "block height is {}, expected {}", found, expected)
+            }
+            Self::TimestampNotAfterParent { parent, found } => write!(
+                f,
+                "block was created at {}, not after its parent at {}",
+                found, parent
+            ),
+            Self::TimestampInFuture { maximum, found } => write!(
+                f,
+                "block was created at {}, expected at most {}",
+                found, maximum
+            ),
+            Self::InvalidMerkleRoot { expected, found } => {
+                write!(f, "block has Merkle root {}, expected {}", found, expected)
+            }
+            Self::TooManyTransactions { maximum, found } => write!(
+                f,
+                "block has {} transactions, expected at most {}",
+                found, maximum
+            ),
+            Self::TooLarge { maximum, found } => {
+                write!(f, "block has {} bytes, expected at most {}", found, maximum)
+            }
+            Self::MissingCoinbase => f.write_str("block does not start with a coinbase"),
+            Self::InvalidCoinbase => f.write_str("coinbase has a sender or signatures"),
+            Self::MisplacedCoinbase(index) => {
+                write!(f, "transaction {} is a coinbase", index)
+            }
+            Self::InvalidTransaction { index, .. } => write!(f, "transaction {} is invalid", index),
+        }
+    }
+}
+
+impl std::error::Error for Rejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidTransaction { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use zgc_common::H256;
+
+/// The change of the canonical chain caused by inserting a block into a
+/// [`crate::Blockchain`].
+///
+/// Extending the canonical chain connects a single block, a block on a side
+/// branch with less work changes nothing. If a side branch gets more work
+/// than the canonical chain, the canonical blocks after the fork point are
+/// disconnected and the blocks of the branch are connected.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reorg {
+    /// Blocks removed from the canonical chain, the old tip first.
+    pub disconnected: Vec<H256>,
+    /// Blocks added to the canonical chain, the new tip last.
+    pub connected: Vec<H256>,
+}
+
+impl Reorg {
+    /// Returns true if the canonical chain didn't change.
+    pub fn is_empty(&self) -> bool {
+        self.disconnected.is_empty() && self.connected.is_empty()
+    }
+
+    /// Combines two successive changes into one.
+    pub(crate) fn append(&mut self, next: Reorg) {
+        for hash in next.disconnected {
+            match self
+                .connected
+                .iter()
+                .position(|connected| *connected == hash)
+            {
+                Some(index) => {
+                    self.connected.remove(index);
+                }
+                None => self.disconnected.push(hash),
+            }
+        }
+        self.connected.extend(next.connected);
+    }
+}
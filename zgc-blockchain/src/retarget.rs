@@ -0,0 +1,283 @@
+// Difficulty retargeting.
+//
+// The target of a block is computed from the timestamps and targets of its
+// ancestors, so blocks keep arriving every `target_spacing` on average
+// while the hashrate changes. Two algorithms are supported:
+//
+// - Periodic, like Bitcoin: the target only changes every `interval` blocks,
+//   by the ratio of the time the last interval took to the time it should
+//   have taken. The ratio is clamped to a factor of 4 in either direction.
+// - LWMA (linearly weighted moving average, see
+//   https://github.com/zawy12/difficulty-algorithms/issues/3): every block
+//   gets the average target of the last `window` blocks, scaled by their
+//   solve times, weighting recent solve times more. Solve times are clamped
+//   to 6 times the spacing, so a single late timestamp has a limited effect.
+
+use std::convert::TryFrom;
+use std::time::Duration;
+use zgc_common::{Timestamp, U256};
+
+/// The maximal factor by which a periodic retarget changes the target.
+const MAX_ADJUSTMENT: u64 = 4;
+
+/// The maximal solve time counted by LWMA, as multiple of the spacing.
+const MAX_SOLVE_TIME: u64 = 6;
+
+/// A difficulty retargeting algorithm.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Retarget {
+    /// Changes the target every `interval` blocks.
+    Periodic { interval: usize },
+    /// Changes the target every block, based on the last `window` blocks.
+    Lwma { window: usize },
+}
+
+impl Retarget {
+    /// The number of ancestors the target of the block at `height` depends
+    /// on, fewer are available for low heights.
+    pub fn ancestors(&self, height: usize) -> usize {
+        match *self {
+            Self::Periodic { interval } if interval > 0 && height.is_multiple_of(interval) => {
+                interval.saturating_add(1)
+            }
+            Self::Periodic { .. } => 1,
+            Self::Lwma { window } => window.saturating_add(1),
+        }
+    }
+
+    /// The target of the block at `height`, given the timestamps and
+    /// targets of its last [`Self::ancestors`] ancestors, oldest first.
+    ///
+    /// The result is never easier than `limit`.
+    pub fn next_target(
+        &self,
+        height: usize,
+        spacing: Duration,
+        limit: U256,
+        ancestors: &[(Timestamp, U256)],
+    ) -> U256 {
+        let parent = match ancestors.last() {
+            Some(&(_, target)) => target,
+            None => return limit,
+        };
+        if ancestors.len() < 2 || self.ancestors(height) == 1 {
+            return parent;
+        }
+
+        // the times in milliseconds saturate, extreme spacings and windows
+        // only make the result less precise
+        let spacing = u64::try_from(spacing.as_millis())
+            .unwrap_or(u64::MAX)
+            .max(1);
+        let count = u64::try_from(ancestors.len() - 1).unwrap_or(u64::MAX);
+        let target = match *self {
+            Self::Periodic { .. } => {
+                let expected = spacing.saturating_mul(count);
+                let actual = ancestors[ancestors.len() - 1]
+                    .0
+                    .as_unix_millis()
+                    .saturating_sub(ancestors[0].0.as_unix_millis())
+                    .clamp(
+                        expected / MAX_ADJUSTMENT,
+                        expected.saturating_mul(MAX_ADJUSTMENT),
+                    );
+                scale(parent, actual, expected)
+            }
+            Self::Lwma { .. } => {
+                let mut average = U256::ZERO;
+                let mut weighted = 0_u64;
+                for (weight, pair) in (1_u64..).zip(ancestors.windows(2)) {
+                    let solve_time = pair[1]
+                        .0
+                        .as_unix_millis()
+                        .saturating_sub(pair[0].0.as_unix_millis())
+                        .clamp(1, MAX_SOLVE_TIME.saturating_mul(spacing));
+                    weighted = weighted.saturating_add(weight.saturating_mul(solve_time));
+                    average = average.saturating_add(pair[1].1 / U256::from(count));
+                }
+                // the sum of the weights 1 to count
+                let weights = if count.is_multiple_of(2) {
+                    (count / 2).saturating_mul(count + 1)
+                } else {
+                    count.saturating_mul(count.div_ceil(2))
+                };
+                scale(average, weighted, weights.saturating_mul(spacing))
+            }
+        };
+        target.clamp(U256::ONE, limit)
+    }
+}
+
+/// `target * numerator / denominator` rounded down, saturating on overflow.
+fn scale(target: U256, numerator: u64, denominator: u64) -> U256 {
+    let (numerator, denominator) = (U256::from(numerator), U256::from(denominator));
+    // the remainder is smaller than the denominator, so its product with
+    // the numerator doesn't overflow
+    let (quotient, remainder) = (target / denominator, target % denominator);
+    quotient
+        .saturating_mul(numerator)
+        .saturating_add(remainder * numerator / denominator)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CompactTarget;
+
+    const SPACING: Duration = Duration::from_secs(10);
+
+    fn limit() -> U256 {
+        U256::MAX >> 8
+    }
+
+    /// Simulates a chain of blocks found by miners with the given hashrates
+    /// in hashes per second, each block takes exactly the expected number
+    /// of hashes to find. Returns the solve times in milliseconds.
+    fn simulate(retarget: Retarget, hashrates: &[u64]) -> Vec<u64> {
+        let mut chain = vec![(Timestamp::UNIX_EPOCH, limit())];
+        let mut solve_times = Vec::new();
+        for (height, &hashrate) in (1..).zip(hashrates) {
+            let start = chain.len().saturating_sub(retarget.ancestors(height));
+            let target = retarget.next_target(height, SPACING, limit(), &chain[start..]);
+            // the target as it is stored in a block header
+            let target = CompactTarget::from_target(target).to_target().unwrap();
+
+            let hashes = (U256::MAX / target).to_u64().unwrap();
+            let solve_time = hashes * 1000 / hashrate;
+            let created_at = chain[chain.len() - 1]
+                .0
+                .checked_add(Duration::from_millis(solve_time))
+                .unwrap();
+            chain.push((created_at, target));
+            solve_times.push(solve_time);
+        }
+        solve_times
+    }
+
+    fn average(solve_times: &[u64]) -> u64 {
+        solve_times.iter().sum::<u64>() / solve_times.len() as u64
+    }
+
+    fn assert_near_spacing(solve_time: u64) {
+        let spacing = SPACING.as_millis() as u64;
+        assert!(
+            solve_time > spacing * 9 / 10 && solve_time < spacing * 11 / 10,
+            "average solve time {}ms",
+            solve_time
+        );
+    }
+
+    #[test]
+    fn periodic() {
+        let retarget = Retarget::Periodic { interval: 10 };
+        assert_eq!(retarget.ancestors(20), 11);
+        assert_eq!(retarget.ancestors(21), 1);
+
+        // the hashrate grows tenfold after 100 blocks
+        let mut hashrates = vec![100; 100];
+        hashrates.extend_from_slice(&[1000; 100]);
+        let solve_times = simulate(retarget, &hashrates);
+
+        assert_near_spacing(average(&solve_times[90..100]));
+        // the target stays the same within an interval
+        assert_eq!(solve_times[100..109], [solve_times[100]; 9]);
+        assert_near_spacing(average(&solve_times[190..]));
+    }
+
+    #[test]
+    fn periodic_clamping() {
+        let retarget = Retarget::Periodic { interval: 10 };
+        let chain: Vec<_> = (0..11)
+            .map(|i| (Timestamp::from_unix_millis(i), limit() >> 4))
+            .collect();
+        assert_eq!(
+            retarget.next_target(10, SPACING, limit(), &chain),
+            (limit() >> 4) / U256::from(MAX_ADJUSTMENT)
+        );
+
+        // the target never gets easier than the limit
+        let chain: Vec<_> = (0..11)
+            .map(|i| (Timestamp::from_unix_secs(i * 1000).unwrap(), limit() >> 1))
+            .collect();
+        assert_eq!(retarget.next_target(10, SPACING, limit(), &chain), limit());
+
+        // in between retargets the target of the parent is kept
+        assert_eq!(
+            retarget.next_target(11, SPACING, limit(), &chain[10..]),
+            limit() >> 1
+        );
+        assert_eq!(retarget.next_target(1, SPACING, limit(), &[]), limit());
+    }
+
+    #[test]
+    fn lwma() {
+        let retarget = Retarget::Lwma { window: 20 };
+        assert_eq!(retarget.ancestors(5), 21);
+
+        let mut hashrates = vec![100; 100];
+        hashrates.extend_from_slice(&[1000; 100]);
+        hashrates.extend_from_slice(&[200; 100]);
+        let solve_times = simulate(retarget, &hashrates);
+
+        assert_near_spacing(average(&solve_times[80..100]));
+        // the target changes with every block after the hashrate jumped
+        assert!(solve_times[101] > solve_times[100]);
+        assert_near_spacing(average(&solve_times[180..200]));
+        assert_near_spacing(average(&solve_times[280..]));
+    }
+
+    #[test]
+    fn lwma_clamping() {
+        let retarget = Retarget::Lwma { window: 4 };
+        let target = limit() >> 8;
+
+        // a timestamp far in the future counts as 6 times the spacing
+        let mut chain: Vec<_> = (0..5)
+            .map(|i| (Timestamp::from_unix_secs(i * 10).unwrap(), target))
+            .collect();
+        let on_time = retarget.next_target(5, SPACING, limit(), &chain);
+        assert_eq!(on_time, target / U256::from(4_u64) * U256::from(4_u64));
+        chain[4].0 = Timestamp::from_unix_secs(1_000_000).unwrap();
+        let late = retarget.next_target(5, SPACING, limit(), &chain);
+        assert_eq!(
+            late,
+            scale(
+                target / U256::from(4_u64) * U256::from(4_u64),
+                10 + 20 + 30 + 4 * 60,
+                100
+            )
+        );
+    }
+
+    #[test]
+    fn extreme_params() {
+        assert_eq!(
+            Retarget::Lwma { window: usize::MAX }.ancestors(1),
+            usize::MAX
+        );
+        assert_eq!(
+            Retarget::Periodic {
+                interval: usize::MAX
+            }
+            .ancestors(0),
+            usize::MAX
+        );
+
+        // blocks far faster than a huge spacing make the target harder by
+        // the maximal adjustment, without overflowing
+        let chain: Vec<_> = (0..11)
+            .map(|i| (Timestamp::from_unix_secs(i * 10).unwrap(), limit() >> 8))
+            .collect();
+        let periodic = Retarget::Periodic { interval: 10 };
+        let target = periodic.next_target(10, Duration::MAX, limit(), &chain);
+        // the saturated times are rounded, so the factor is not exactly 4
+        let quarter = (limit() >> 8) / U256::from(MAX_ADJUSTMENT);
+        assert!(target <= quarter && target > quarter - (quarter >> 32));
+        let lwma = Retarget::Lwma { window: 10 };
+        let target = lwma.next_target(10, Duration::MAX, limit(), &chain);
+        assert!(target >= U256::ONE && target < limit() >> 8);
+        // as do huge windows of which only a few blocks exist
+        let lwma = Retarget::Lwma { window: usize::MAX };
+        assert!(lwma.next_target(10, SPACING, limit(), &chain) <= limit());
+    }
+}
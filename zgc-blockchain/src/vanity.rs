@@ -0,0 +1,283 @@
+// Vanity address search.
+//
+// Random private keys are generated on multiple threads until the hex form
+// of a derived wallet address starts and ends with the requested pattern.
+// Every additional hex character in the pattern makes the search 16 times
+// harder, so long patterns are only feasible with a lot of patience.
+
+use crate::miner::StopOnDrop;
+use crate::{keygen, Error, Multisig, PrivateKey, Wallet};
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use zgc_common::Alphabet;
+
+/// The time between two progress reports of [`search`].
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// The time between two checks of whether a match was found or the search
+/// was cancelled.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// The length of an address in hex characters.
+const ADDRESS_HEX_LEN: usize = 40;
+
+/// A hex prefix and suffix that a vanity address has to match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    prefix: String,
+    suffix: String,
+}
+
+impl Pattern {
+    pub fn new(prefix: &str, suffix: &str) -> Result<Self, Error> {
+        let prefix = prefix.trim_start_matches("0x").to_ascii_lowercase();
+        let suffix = suffix.to_ascii_lowercase();
+
+        // positions are relative to the part of the pattern they are in
+        for part in [&prefix, &suffix] {
+            if let Some((position, character)) = part
+                .chars()
+                .enumerate()
+                .find(|(_, c)| !c.is_ascii_hexdigit())
+            {
+                return Err(zgc_common::Error::InvalidCharacter {
+                    alphabet: Alphabet::Hex,
+                    character,
+                    position,
+                }
+                .into());
+            }
+        }
+
+        if prefix.len() + suffix.len() > ADDRESS_HEX_LEN {
+            return Err(zgc_common::Error::TooLong {
+                maximum: ADDRESS_HEX_LEN,
+                found: prefix.len() + suffix.len(),
+            }
+            .into());
+        }
+
+        Ok(Self { prefix, suffix })
+    }
+
+    pub fn matches(&self, address_hex: &str) -> bool {
+        address_hex.starts_with(&self.prefix) && address_hex.ends_with(&self.suffix)
+    }
+
+    /// The expected number of attempts until a random address matches the
+    /// pattern.
+    pub fn difficulty(&self) -> f64 {
+        16_f64.powi((self.prefix.len() + self.suffix.len()) as i32)
+    }
+}
+
+/// A snapshot of a running vanity search.
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub attempts: u64,
+    pub elapsed: Duration,
+    pub difficulty: f64,
+}
+
+impl Progress {
+    pub fn attempts_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.attempts as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// The expected time until a match is found at the current rate.
+    ///
+    /// Every attempt is independent, so the expectation doesn't depend on
+    /// the attempts already made. Returns `None` until the rate is known and
+    /// if the time is too long to be represented.
+    pub fn expected_time(&self) -> Option<Duration> {
+        let rate = self.attempts_per_second();
+        if rate > 0.0 {
+            Duration::try_from_secs_f64(self.difficulty / rate).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Searches for a wallet whose address matches `pattern` on `threads`
+/// worker threads.
+///
+/// `report` is called on the calling thread roughly every second with the
+/// current progress. Setting `cancel` stops the search, in which case
+/// `None` is returned.
+pub fn search(
+    pattern: &Pattern,
+    threads: usize,
+    cancel: &AtomicBool,
+    mut report: impl FnMut(&Progress),
+) -> Option<(Wallet, PrivateKey)> {
+    let attempts = AtomicU64::new(0);
+    let done = AtomicBool::new(false);
+    let found = Mutex::new(None);
+    let start = Instant::now();
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| work(pattern, &attempts, &done, cancel, &found));
+        }
+        // stops the workers when the search ends, also if `report` panics,
+        // otherwise the scope would wait for them forever
+        let _stop = StopOnDrop(&done);
+
+        let mut last_report = start;
+        while !done.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+            thread::sleep(POLL_INTERVAL);
+
+            if last_report.elapsed() >= REPORT_INTERVAL {
+                last_report = Instant::now();
+                report(&Progress {
+                    attempts: attempts.load(Ordering::Relaxed),
+                    elapsed: start.elapsed(),
+                    difficulty: pattern.difficulty(),
+                });
+            }
+        }
+    });
+
+    // unwrap is fine because the workers don't panic while holding the lock
+    found.into_inner().unwrap()
+}
+
+fn work(
+    pattern: &Pattern,
+    attempts: &AtomicU64,
+    done: &AtomicBool,
+    cancel: &AtomicBool,
+    found: &Mutex<Option<(Wallet, PrivateKey)>>,
+) {
+    while !done.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+        let private_key = PrivateKey::random();
+        let public_key = match keygen(&private_key) {
+            Ok(public_key) => public_key,
+            Err(_) => continue,
+        };
+        attempts.fetch_add(1, Ordering::Relaxed);
+
+        if pattern.matches(&Multisig::single(public_key).address().to_string()) {
+            let mut found = found.lock().unwrap();
+            if found.is_none() {
+                *found = Some((Wallet { public_key }, private_key));
+            }
+            done.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pattern() {
+        let pattern = Pattern::new("0xAb", "c").expect("invalid pattern");
+        assert!(pattern.matches("ab0000000000000000000000000000000000000c"));
+        assert!(!pattern.matches("ba0000000000000000000000000000000000000c"));
+        assert!(!pattern.matches("ab0000000000000000000000000000000000000d"));
+        assert_eq!(pattern.difficulty(), 4096.0);
+
+        assert_eq!(
+            Pattern::new("abg", ""),
+            Err(Error::Common(zgc_common::Error::InvalidCharacter {
+                alphabet: Alphabet::Hex,
+                character: 'g',
+                position: 2
+            }))
+        );
+        // the position is relative to the suffix
+        assert_eq!(
+            Pattern::new("abc", "0x1"),
+            Err(Error::Common(zgc_common::Error::InvalidCharacter {
+                alphabet: Alphabet::Hex,
+                character: 'x',
+                position: 1
+            }))
+        );
+        assert_eq!(
+            Pattern::new(&"a".repeat(30), &"b".repeat(11)),
+            Err(Error::Common(zgc_common::Error::TooLong {
+                maximum: 40,
+                found: 41
+            }))
+        );
+    }
+
+    #[test]
+    fn progress() {
+        let progress = Progress {
+            attempts: 2000,
+            elapsed: Duration::from_secs(2),
+            difficulty: 4096.0,
+        };
+        assert_eq!(progress.attempts_per_second(), 1000.0);
+        assert_eq!(progress.expected_time(), Some(Duration::from_millis(4096)));
+
+        let progress = Progress {
+            attempts: 0,
+            elapsed: Duration::from_secs(0),
+            difficulty: 16.0,
+        };
+        assert_eq!(progress.expected_time(), None);
+
+        // the expected time of long patterns exceeds what a Duration holds
+        let pattern = Pattern::new(&"0".repeat(24), "").unwrap();
+        let progress = Progress {
+            attempts: 1000,
+            elapsed: Duration::from_secs(1),
+            difficulty: pattern.difficulty(),
+        };
+        assert_eq!(progress.expected_time(), None);
+    }
+
+    #[test]
+    fn search_match() {
+        let pattern = Pattern::new("a", "").unwrap();
+        let cancel = AtomicBool::new(false);
+
+        let (wallet, private_key) = search(&pattern, 2, &cancel, |_| {}).expect("not found");
+        assert!(wallet.address().to_string().starts_with('a'));
+        assert_eq!(
+            Wallet::new(private_key).unwrap().address(),
+            wallet.address()
+        );
+    }
+
+    #[test]
+    fn search_cancel() {
+        // practically impossible to find
+        let pattern = Pattern::new(&"0".repeat(40), "").unwrap();
+        let cancel = AtomicBool::new(false);
+
+        let mut reports = 0;
+        let result = search(&pattern, 2, &cancel, |progress| {
+            assert!(progress.attempts > 0);
+            reports += 1;
+            cancel.store(true, Ordering::Relaxed);
+        });
+        assert!(result.is_none());
+        assert_eq!(reports, 1);
+    }
+
+    #[test]
+    fn search_panic() {
+        let pattern = Pattern::new(&"0".repeat(40), "").unwrap();
+        let cancel = AtomicBool::new(false);
+
+        // the workers stop, so the panic reaches the caller instead of the
+        // search hanging
+        let result = std::panic::catch_unwind(|| {
+            search(&pattern, 2, &cancel, |_| panic!("report failed"));
+        });
+        assert!(result.is_err());
+    }
+}
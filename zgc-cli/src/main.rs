@@ -0,0 +1,123 @@
+// Command line interface of zgencoin.
+//
+// to see the available subcommands run
+//
+// cargo run --bin zgc -- help
+
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use zgc_blockchain::vanity::{self, Pattern};
+use zgc_common::Network;
+use zgc_crypto::address::{AddressEncoding, AddressFormat, EncodedAddress};
+
+const USAGE: &str = "\
+Usage: zgc <SUBCOMMAND> [OPTIONS]
+
+Subcommands:
+    vanity    Searches for a wallet whose address matches a hex pattern
+    help      Prints this message
+
+Options of vanity:
+    --prefix <HEX>        Hex prefix of the address
+    --suffix <HEX>        Hex suffix of the address
+    --threads <N>         Number of worker threads [default: number of cores]
+    --timeout <SECONDS>   Gives up the search after the given time
+    --testnet             Exports the private key for the testnet";
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    let result = match args.first().map(String::as_str) {
+        Some("vanity") => vanity(&args[1..]),
+        Some("help") | Some("--help") | None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(subcommand) => Err(format!("unknown subcommand {:?}\n\n{}", subcommand, USAGE).into()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        let mut source = e.source();
+        while let Some(e) = source {
+            eprintln!("  caused by: {}", e);
+            source = e.source();
+        }
+        std::process::exit(1);
+    }
+}
+
+fn vanity(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut prefix = String::new();
+    let mut suffix = String::new();
+    let mut threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut timeout = None;
+    let mut network = Network::Mainnet;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--prefix" => prefix = value(arg, args.next())?,
+            "--suffix" => suffix = value(arg, args.next())?,
+            "--threads" => {
+                threads = value(arg, args.next())?
+                    .parse()
+                    .map_err(|e| format!("invalid number of threads: {}", e))?
+            }
+            "--timeout" => {
+                let seconds = value(arg, args.next())?
+                    .parse()
+                    .map_err(|e| format!("invalid timeout: {}", e))?;
+                timeout = Some(Duration::from_secs(seconds));
+            }
+            "--testnet" => network = Network::Testnet,
+            _ => return Err(format!("unknown option {:?}", arg).into()),
+        }
+    }
+
+    let pattern = Pattern::new(&prefix, &suffix)?;
+    eprintln!(
+        "searching with {} threads, {:.0} attempts expected",
+        threads,
+        pattern.difficulty()
+    );
+
+    let cancel = AtomicBool::new(false);
+    let result = vanity::search(&pattern, threads, &cancel, |progress| {
+        let expected = progress
+            .expected_time()
+            .map_or("unknown".to_string(), |t| format!("{}s", t.as_secs()));
+        eprintln!(
+            "{} attempts, {:.0} attempts/s, expected time: {}",
+            progress.attempts,
+            progress.attempts_per_second(),
+            expected
+        );
+
+        if timeout.is_some_and(|timeout| progress.elapsed >= timeout) {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    });
+
+    match result {
+        Some((wallet, private_key)) => {
+            let encoding = AddressEncoding::new(AddressFormat::Bech32m, network);
+            println!("address: {}", wallet.address());
+            println!(
+                "checksummed address: {}",
+                EncodedAddress::new(wallet.address(), encoding)
+            );
+            println!("private key: {}", private_key.to_wif(network));
+            Ok(())
+        }
+        None => Err("search timed out".into()),
+    }
+}
+
+fn value(option: &str, value: Option<&String>) -> Result<String, String> {
+    value
+        .cloned()
+        .ok_or_else(|| format!("missing value of option {}", option))
+}
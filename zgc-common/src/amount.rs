@@ -0,0 +1,337 @@
+use crate::encoding::{Decode, Encode};
+use crate::error::{Alphabet, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+/// A number of coins, stored as an integer number of base units.
+///
+/// One ZGC is 10^8 base units and an amount never exceeds the maximum
+/// supply, so the arithmetic is checked against [`Amount::MAX`] instead of
+/// `u64::MAX`. The textual form is a decimal number of ZGC, e.g. `1.5 ZGC`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, std::hash::Hash)]
+pub struct Amount(u64);
+
+/// A unit that amounts can be formatted and parsed in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Denomination {
+    Zgc,
+    MilliZgc,
+    MicroZgc,
+    BaseUnit,
+}
+
+impl Denomination {
+    /// The number of fractional digits of the unit, i.e. the base units
+    /// per unit are 10 to the power of this.
+    pub fn decimals(self) -> u32 {
+        match self {
+            Self::Zgc => 8,
+            Self::MilliZgc => 5,
+            Self::MicroZgc => 2,
+            Self::BaseUnit => 0,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Zgc => "ZGC",
+            Self::MilliZgc => "mZGC",
+            Self::MicroZgc => "uZGC",
+            Self::BaseUnit => "base",
+        }
+    }
+
+    fn from_symbol(symbol: &str) -> Result<Self, Error> {
+        [Self::Zgc, Self::MilliZgc, Self::MicroZgc, Self::BaseUnit]
+            .iter()
+            .copied()
+            .find(|denomination| denomination.symbol() == symbol)
+            .ok_or_else(|| Error::UnknownDenomination(symbol.to_string()))
+    }
+}
+
+impl fmt::Display for Denomination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+impl Amount {
+    /// The base units of one ZGC.
+    pub const COIN: u64 = 100_000_000;
+    pub const ZERO: Self = Self(0);
+    /// The maximum supply of 21 million ZGC.
+    pub const MAX: Self = Self(21_000_000 * Self::COIN);
+
+    pub fn from_base_units(base_units: u64) -> Result<Self, Error> {
+        if base_units > Self::MAX.0 {
+            return Err(Error::Overflow);
+        }
+        Ok(Self(base_units))
+    }
+
+    pub fn from_zgc(zgc: u64) -> Result<Self, Error> {
+        zgc.checked_mul(Self::COIN)
+            .ok_or(Error::Overflow)
+            .and_then(Self::from_base_units)
+    }
+
+    pub const fn base_units(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0
+            .checked_add(other.0)
+            .and_then(|sum| Self::from_base_units(sum).ok())
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    pub fn checked_mul(self, factor: u64) -> Option<Self> {
+        self.0
+            .checked_mul(factor)
+            .and_then(|product| Self::from_base_units(product).ok())
+    }
+
+    /// Formats the amount as a decimal number of `denomination` without
+    /// trailing zeros and without the symbol.
+    pub fn to_string_in(self, denomination: Denomination) -> String {
+        let unit = 10_u64.pow(denomination.decimals());
+        let whole = self.0 / unit;
+        let fraction = self.0 % unit;
+
+        if fraction == 0 {
+            return whole.to_string();
+        }
+        let digits = format!(
+            "{:0width$}",
+            fraction,
+            width = denomination.decimals() as usize
+        );
+        format!("{}.{}", whole, digits.trim_end_matches('0'))
+    }
+
+    /// Parses a decimal number of `denomination` without a symbol, it must
+    /// not have more fractional digits than the denomination.
+    pub fn from_str_in(string: &str, denomination: Denomination) -> Result<Self, Error> {
+        let decimals = denomination.decimals() as usize;
+        let (whole, fraction) = match string.find('.') {
+            Some(point) => (&string[..point], &string[point + 1..]),
+            None => (string, ""),
+        };
+
+        if whole.is_empty() {
+            return Err(Error::Empty);
+        }
+        if fraction.len() > decimals {
+            return Err(Error::TooLong {
+                maximum: decimals,
+                found: fraction.len(),
+            });
+        }
+
+        let whole = parse_digits(whole, 0)?;
+        // the fraction is scaled up to base units
+        let fraction = parse_digits(fraction, string.len() - fraction.len())?
+            * 10_u64.pow((decimals - fraction.len()) as u32);
+
+        let base_units = whole
+            .checked_mul(10_u64.pow(denomination.decimals()))
+            .and_then(|whole| whole.checked_add(fraction))
+            .ok_or(Error::Overflow)?;
+        Self::from_base_units(base_units)
+    }
+}
+
+/// Parses decimal digits, `offset` is the position of the first digit in
+/// the parsed string.
+fn parse_digits(digits: &str, offset: usize) -> Result<u64, Error> {
+    digits
+        .chars()
+        .enumerate()
+        .try_fold(0_u64, |number, (position, character)| {
+            let digit = character.to_digit(10).ok_or(Error::InvalidCharacter {
+                alphabet: Alphabet::Decimal,
+                character,
+                position: offset + position,
+            })?;
+
+            number
+                .checked_mul(10)
+                .and_then(|number| number.checked_add(u64::from(digit)))
+                .ok_or(Error::Overflow)
+        })
+}
+
+/// Formats the amount in ZGC with the symbol, e.g. `1.5 ZGC`.
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.to_string_in(Denomination::Zgc),
+            Denomination::Zgc
+        )
+    }
+}
+
+/// Parses a decimal number followed by an optional space and symbol, the
+/// denomination defaults to ZGC.
+impl FromStr for Amount {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.split_once(' ') {
+            Some((number, symbol)) => Self::from_str_in(number, Denomination::from_symbol(symbol)?),
+            None => Self::from_str_in(string, Denomination::Zgc),
+        }
+    }
+}
+
+impl Encode for Amount {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.encode(writer)
+    }
+}
+
+/// Decoding rejects amounts above [`Amount::MAX`].
+impl Decode for Amount {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Self::from_base_units(u64::decode(reader)?)
+    }
+}
+
+/// Human-readable formats get the amount as a string like `"1.5 ZGC"`,
+/// binary formats get the base units.
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let result = if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?.parse()
+        } else {
+            Self::from_base_units(u64::deserialize(deserializer)?)
+        };
+        result.map_err(|e| serde::de::Error::custom(format!("Deserialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn zgc(string: &str) -> Amount {
+        string.parse().unwrap()
+    }
+
+    #[test]
+    fn arithmetic() {
+        let one = Amount::from_zgc(1).unwrap();
+        assert_eq!(one.base_units(), Amount::COIN);
+        assert_eq!(one.checked_add(one), Amount::from_zgc(2).ok());
+        assert_eq!(one.checked_sub(one), Some(Amount::ZERO));
+        assert_eq!(Amount::ZERO.checked_sub(one), None);
+        assert_eq!(one.checked_mul(3), Amount::from_zgc(3).ok());
+
+        // the bound is the maximum supply, not u64::MAX
+        assert_eq!(
+            Amount::MAX.checked_add(Amount::from_base_units(1).unwrap()),
+            None
+        );
+        assert_eq!(one.checked_mul(21_000_001), None);
+        assert_eq!(one.checked_mul(u64::MAX), None);
+        assert_eq!(Amount::from_zgc(21_000_001), Err(Error::Overflow));
+        assert_eq!(Amount::from_zgc(u64::MAX), Err(Error::Overflow));
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(zgc("1.5").to_string(), "1.5 ZGC");
+        assert_eq!(zgc("1.5").base_units(), 150_000_000);
+        assert_eq!(Amount::ZERO.to_string(), "0 ZGC");
+        assert_eq!(
+            Amount::from_base_units(1).unwrap().to_string(),
+            "0.00000001 ZGC"
+        );
+        assert_eq!(Amount::MAX.to_string(), "21000000 ZGC");
+        assert_eq!(zgc("1.5").to_string_in(Denomination::MilliZgc), "1500");
+        assert_eq!(zgc("0.000123").to_string_in(Denomination::MicroZgc), "123");
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(zgc("1.5 ZGC"), zgc("1.50000000"));
+        assert_eq!(zgc("1500 mZGC"), zgc("1.5"));
+        assert_eq!(zgc("0.5 uZGC"), Amount::from_base_units(50).unwrap());
+        assert_eq!(zgc("7 base"), Amount::from_base_units(7).unwrap());
+        assert_eq!(zgc("3."), Amount::from_zgc(3).unwrap());
+
+        assert_eq!(
+            "0.000000001".parse::<Amount>(),
+            Err(Error::TooLong {
+                maximum: 8,
+                found: 9
+            })
+        );
+        assert_eq!(
+            "1.5 BTC".parse::<Amount>(),
+            Err(Error::UnknownDenomination("BTC".to_string()))
+        );
+        assert_eq!(
+            "1,5".parse::<Amount>(),
+            Err(Error::InvalidCharacter {
+                alphabet: Alphabet::Decimal,
+                character: ',',
+                position: 1
+            })
+        );
+        assert_eq!(
+            "1.-5".parse::<Amount>(),
+            Err(Error::InvalidCharacter {
+                alphabet: Alphabet::Decimal,
+                character: '-',
+                position: 2
+            })
+        );
+        assert_eq!(".5".parse::<Amount>(), Err(Error::Empty));
+        assert_eq!("".parse::<Amount>(), Err(Error::Empty));
+        assert_eq!("21000000.00000001".parse::<Amount>(), Err(Error::Overflow));
+        assert_eq!(
+            "184467440737.09551616".parse::<Amount>(),
+            Err(Error::Overflow)
+        );
+    }
+
+    #[test]
+    fn serde_and_encoding() {
+        let amount = zgc("1.5");
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "\"1.5 ZGC\"");
+        assert_eq!(serde_json::from_str::<Amount>("\"1.5\"").unwrap(), amount);
+        assert!(serde_json::from_str::<Amount>("\"21000001\"").is_err());
+
+        let bytes = bincode::serialize(&amount).unwrap();
+        assert_eq!(bytes, 150_000_000_u64.to_le_bytes());
+        assert_eq!(bincode::deserialize::<Amount>(&bytes).unwrap(), amount);
+        assert!(bincode::deserialize::<Amount>(&u64::MAX.to_le_bytes()).is_err());
+
+        assert_eq!(amount.to_bytes(), 150_000_000_u64.to_le_bytes());
+        assert_eq!(Amount::from_bytes(&amount.to_bytes()), Ok(amount));
+        assert_eq!(
+            Amount::from_bytes(&u64::MAX.to_le_bytes()),
+            Err(Error::Overflow)
+        );
+    }
+}
@@ -0,0 +1,115 @@
+// Base58 encoding with the Bitcoin alphabet.
+//
+// The alphabet leaves out the characters that are easily confused when
+// written down (0, O, I and l). The input is interpreted as a single big
+// endian number that is converted to base 58, and every leading zero byte is
+// represented by a leading '1' character.
+
+use crate::error::{Alphabet, Error};
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encodes a byte slice into a base58 `String`.
+pub fn encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+
+    // base58 digits in little endian order
+    let mut digits = Vec::<u8>::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &byte in &bytes[zeros..] {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&digit| ALPHABET[digit as usize] as char),
+        )
+        .collect()
+}
+
+/// Decodes a base58 string slice into bytes.
+pub fn decode(string: &str) -> Result<Vec<u8>, Error> {
+    let zeros = string.chars().take_while(|&c| c == '1').count();
+
+    // bytes in little endian order
+    let mut bytes = Vec::<u8>::with_capacity(string.len() * 733 / 1000 + 1);
+    for (position, character) in string.chars().enumerate() {
+        let value = ALPHABET
+            .iter()
+            .position(|&c| c as char == character)
+            .ok_or(Error::InvalidCharacter {
+                alphabet: Alphabet::Base58,
+                character,
+                position,
+            })?;
+
+        let mut carry = value as u32;
+        for byte in bytes.iter_mut() {
+            carry += u32::from(*byte) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut result = vec![0_u8; zeros];
+    result.extend(bytes.iter().rev());
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encoding() {
+        assert_eq!(encode(&[]), "");
+        assert_eq!(encode(&[0]), "1");
+        assert_eq!(encode(&[0, 0, 1]), "112");
+        assert_eq!(encode(b"hello world"), "StV1DL6CwTryKyV");
+        assert_eq!(encode(&[0xff; 4]), "7YXq9G");
+    }
+
+    #[test]
+    fn decoding() {
+        assert_eq!(decode(""), Ok(vec![]));
+        assert_eq!(decode("1"), Ok(vec![0]));
+        assert_eq!(decode("112"), Ok(vec![0, 0, 1]));
+        assert_eq!(decode("StV1DL6CwTryKyV"), Ok(b"hello world".to_vec()));
+        assert_eq!(decode("7YXq9G"), Ok(vec![0xff; 4]));
+
+        assert_eq!(
+            decode("StV1Dl6CwTryKyV"),
+            Err(Error::InvalidCharacter {
+                alphabet: Alphabet::Base58,
+                character: 'l',
+                position: 5
+            })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(decode(&encode(&bytes)), Ok(bytes));
+
+        let bytes = [0, 0, 0, 0xab, 0, 0xcd];
+        assert_eq!(decode(&encode(&bytes)), Ok(bytes.to_vec()));
+    }
+}
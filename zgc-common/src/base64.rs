@@ -0,0 +1,122 @@
+// Base64 encoding with the standard alphabet and padding (RFC 4648).
+//
+// Every 3 input bytes are split into 4 groups of 6 bits, each of which is
+// represented by one character of the alphabet. An incomplete last group is
+// filled up with '=' characters, so the length of an encoded string is
+// always a multiple of 4.
+
+use crate::error::{Alphabet, Error};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const PADDING: char = '=';
+
+/// Encodes a byte slice into a base64 `String`.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0_u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+
+        // n bytes are represented by n + 1 characters
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let value = (group >> (18 - 6 * i)) & 0x3f;
+                encoded.push(ALPHABET[value as usize] as char);
+            } else {
+                encoded.push(PADDING);
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decodes a padded base64 string slice into bytes.
+pub fn decode(string: &str) -> Result<Vec<u8>, Error> {
+    let data = string.trim_end_matches(PADDING);
+    if !string.len().is_multiple_of(4) || string.len() - data.len() > 2 {
+        return Err(Error::InvalidPadding(Alphabet::Base64));
+    }
+
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let mut group = 0_u32;
+    for (position, character) in data.chars().enumerate() {
+        let value = ALPHABET
+            .iter()
+            .position(|&c| c as char == character)
+            .ok_or(Error::InvalidCharacter {
+                alphabet: Alphabet::Base64,
+                character,
+                position,
+            })?;
+
+        group = group << 6 | value as u32;
+        if position % 4 == 3 {
+            bytes.extend_from_slice(&group.to_be_bytes()[1..]);
+            group = 0;
+        }
+    }
+
+    // the unused low bits of the last character have to be zero, otherwise
+    // multiple strings would decode to the same bytes
+    match data.len() % 4 {
+        2 if group & 0xf == 0 => bytes.push((group >> 4) as u8),
+        3 if group & 0x3 == 0 => bytes.extend_from_slice(&((group >> 2) as u16).to_be_bytes()),
+        0 => {}
+        _ => return Err(Error::InvalidPadding(Alphabet::Base64)),
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encoding() {
+        // test vectors from RFC 4648
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foo"), "Zm9v");
+        assert_eq!(encode(b"foob"), "Zm9vYg==");
+        assert_eq!(encode(b"fooba"), "Zm9vYmE=");
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(encode(&[0xfb, 0xff]), "+/8=");
+    }
+
+    #[test]
+    fn decoding() {
+        assert_eq!(decode(""), Ok(vec![]));
+        assert_eq!(decode("Zg=="), Ok(b"f".to_vec()));
+        assert_eq!(decode("Zm8="), Ok(b"fo".to_vec()));
+        assert_eq!(decode("Zm9vYmFy"), Ok(b"foobar".to_vec()));
+        assert_eq!(decode("+/8="), Ok(vec![0xfb, 0xff]));
+
+        assert_eq!(decode("Zm9"), Err(Error::InvalidPadding(Alphabet::Base64)));
+        assert_eq!(
+            decode("Zm9-"),
+            Err(Error::InvalidCharacter {
+                alphabet: Alphabet::Base64,
+                character: '-',
+                position: 3
+            })
+        );
+        // the unused bits of 'h' are not zero
+        assert_eq!(decode("Zh=="), Err(Error::InvalidPadding(Alphabet::Base64)));
+        assert_eq!(decode("Z==="), Err(Error::InvalidPadding(Alphabet::Base64)));
+        assert!(decode("Zg=a").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        for len in 0..6 {
+            assert_eq!(decode(&encode(&bytes[..len])), Ok(bytes[..len].to_vec()));
+        }
+        assert_eq!(decode(&encode(&bytes)), Ok(bytes));
+    }
+}
@@ -0,0 +1,255 @@
+// Bech32 (BIP-173) and Bech32m (BIP-350) encodings.
+//
+// An encoded string consists of a human-readable part (hrp), the separator
+// '1' and the data part, where every character carries 5 bits. The last 6
+// characters are a BCH checksum over the hrp and the data that detects any
+// error affecting at most 4 characters. Bech32 and Bech32m only differ in
+// the constant the checksum is xor-ed with.
+
+use crate::error::{Alphabet, Error};
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+const CHECKSUM_LEN: usize = 6;
+const MAX_LEN: usize = 90;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Self::Bech32 => 1,
+            Self::Bech32m => 0x2bc830a3,
+        }
+    }
+}
+
+/// Encodes the data with the given human-readable part.
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> Result<String, Error> {
+    validate_hrp(hrp)?;
+    let hrp = hrp.to_ascii_lowercase();
+
+    let mut values = convert_bits(data, 8, 5, true)?;
+    let len = hrp.len() + 1 + values.len() + CHECKSUM_LEN;
+    if len > MAX_LEN {
+        return Err(Error::TooLong {
+            maximum: MAX_LEN,
+            found: len,
+        });
+    }
+
+    let checksum = checksum(&hrp, &values, variant);
+    values.extend_from_slice(&checksum);
+
+    let mut encoded = hrp;
+    encoded.push('1');
+    encoded.extend(values.iter().map(|&value| CHARSET[value as usize] as char));
+    Ok(encoded)
+}
+
+/// Decodes a bech32 or bech32m string slice into its lowercase
+/// human-readable part, data and variant.
+pub fn decode(string: &str) -> Result<(String, Vec<u8>, Variant), Error> {
+    if string.len() > MAX_LEN {
+        return Err(Error::TooLong {
+            maximum: MAX_LEN,
+            found: string.len(),
+        });
+    }
+
+    if string.chars().any(|c| c.is_ascii_lowercase())
+        && string.chars().any(|c| c.is_ascii_uppercase())
+    {
+        return Err(Error::MixedCase);
+    }
+
+    let string = string.to_ascii_lowercase();
+    let separator = string.rfind('1').ok_or(Error::MissingSeparator)?;
+    let (hrp, data) = (&string[..separator], &string[separator + 1..]);
+    validate_hrp(hrp)?;
+
+    if data.len() < CHECKSUM_LEN {
+        return Err(Error::TooShort {
+            minimum: separator + 1 + CHECKSUM_LEN,
+            found: string.len(),
+        });
+    }
+
+    let values = data
+        .chars()
+        .enumerate()
+        .map(|(i, character)| {
+            CHARSET
+                .iter()
+                .position(|&c| c as char == character)
+                .map(|value| value as u8)
+                .ok_or(Error::InvalidCharacter {
+                    alphabet: Alphabet::Bech32,
+                    character,
+                    position: separator + 1 + i,
+                })
+        })
+        .collect::<Result<Vec<u8>, Error>>()?;
+
+    let variant = match polymod(&expand_hrp(hrp), &values) {
+        c if c == Variant::Bech32.constant() => Variant::Bech32,
+        c if c == Variant::Bech32m.constant() => Variant::Bech32m,
+        _ => return Err(Error::InvalidBech32Checksum),
+    };
+
+    let data = convert_bits(&values[..values.len() - CHECKSUM_LEN], 5, 8, false)?;
+    Ok((hrp.to_string(), data, variant))
+}
+
+/// The human-readable part has to consist of 1 to 83 printable ASCII
+/// characters.
+fn validate_hrp(hrp: &str) -> Result<(), Error> {
+    if hrp.is_empty() || hrp.len() > 83 || hrp.chars().any(|c| !('!'..='~').contains(&c)) {
+        return Err(Error::InvalidHumanReadablePart(hrp.to_string()));
+    }
+
+    Ok(())
+}
+
+fn polymod(hrp: &[u8], values: &[u8]) -> u32 {
+    hrp.iter().chain(values).fold(1_u32, |checksum, &value| {
+        let top = checksum >> 25;
+        let checksum = ((checksum & 0x01ff_ffff) << 5) ^ u32::from(value);
+        GENERATOR
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (top >> i) & 1 == 1)
+            .fold(checksum, |checksum, (_, generator)| checksum ^ generator)
+    })
+}
+
+/// The hrp is mixed into the checksum by its high bits, a zero separator and
+/// its low bits.
+fn expand_hrp(hrp: &str) -> Vec<u8> {
+    hrp.bytes()
+        .map(|b| b >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|b| b & 0x1f))
+        .collect()
+}
+
+fn checksum(hrp: &str, values: &[u8], variant: Variant) -> [u8; CHECKSUM_LEN] {
+    let mut padded = values.to_vec();
+    padded.extend_from_slice(&[0; CHECKSUM_LEN]);
+    let polymod = polymod(&expand_hrp(hrp), &padded) ^ variant.constant();
+
+    let mut checksum = [0_u8; CHECKSUM_LEN];
+    checksum
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, value)| *value = ((polymod >> (5 * (5 - i))) & 0x1f) as u8);
+    checksum
+}
+
+/// Regroups a stream of `from` bit wide values into `to` bit wide values.
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>, Error> {
+    let mut accumulator = 0_u32;
+    let mut bits = 0_u32;
+    let mut result = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    let max_value = (1_u32 << to) - 1;
+
+    for &value in data {
+        accumulator = (accumulator << from) | u32::from(value);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            result.push(((accumulator >> bits) & max_value) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            result.push(((accumulator << (to - bits)) & max_value) as u8);
+        }
+    } else if bits >= from || (accumulator << (to - bits)) & max_value != 0 {
+        return Err(Error::InvalidPadding(Alphabet::Bech32));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid_checksums() {
+        // test vectors from BIP-173 and BIP-350
+        assert_eq!(
+            decode("A12UEL5L"),
+            Ok(("a".to_string(), vec![], Variant::Bech32))
+        );
+        assert_eq!(
+            decode("a12uel5l"),
+            Ok(("a".to_string(), vec![], Variant::Bech32))
+        );
+        assert_eq!(
+            decode("A1LQFN3A"),
+            Ok(("a".to_string(), vec![], Variant::Bech32m))
+        );
+        assert!(decode("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw").is_ok());
+        assert!(decode("abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx").is_ok());
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(
+            encode("a", &[], Variant::Bech32),
+            Ok("a12uel5l".to_string())
+        );
+        assert_eq!(
+            encode("A", &[], Variant::Bech32m),
+            Ok("a1lqfn3a".to_string())
+        );
+
+        let data = (0..20).collect::<Vec<u8>>();
+        let encoded = encode("zgc", &data, Variant::Bech32).unwrap();
+        assert_eq!(encoded, "zgc1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnpfmcpg");
+        assert_eq!(
+            decode(&encoded),
+            Ok(("zgc".to_string(), data.clone(), Variant::Bech32))
+        );
+
+        let encoded = encode("zgc", &data, Variant::Bech32m).unwrap();
+        assert_eq!(
+            decode(&encoded),
+            Ok(("zgc".to_string(), data, Variant::Bech32m))
+        );
+    }
+
+    #[test]
+    fn invalid_strings() {
+        assert_eq!(decode("a12uel5m"), Err(Error::InvalidBech32Checksum));
+        assert_eq!(
+            decode("zgc1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnpfmcpb"),
+            Err(Error::InvalidCharacter {
+                alphabet: Alphabet::Bech32,
+                character: 'b',
+                position: 41
+            })
+        );
+        assert_eq!(decode("A12uEL5L"), Err(Error::MixedCase));
+        assert_eq!(decode("pzry9x0s0muk"), Err(Error::MissingSeparator));
+        assert_eq!(
+            decode("1pzry9x0s0muk"),
+            Err(Error::InvalidHumanReadablePart(String::new()))
+        );
+        assert_eq!(
+            decode("a1uel5l"),
+            Err(Error::TooShort {
+                minimum: 8,
+                found: 7
+            })
+        );
+        assert!(encode("", &[], Variant::Bech32).is_err());
+    }
+}
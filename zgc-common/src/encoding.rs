@@ -0,0 +1,294 @@
+// Canonical binary encoding.
+//
+// Everything that is hashed or signed (block headers, transactions) is
+// hashed over this encoding, so it has to be unambiguous and must never
+// change for existing versions. The rules are:
+//
+// - integers are encoded in fixed width little endian, `usize` as `u64`
+// - `bool` is a single byte, 0 or 1
+// - `Hash<N>` is its N bytes as is
+// - `Option<T>` is a 0 byte for `None`, or a 1 byte followed by the value
+// - `Vec<T>` is its length as `u32` followed by the elements
+//
+// Decoding rejects every input that is not the encoding of a value, so
+// every value has exactly one encoding. See docs/consensus-encoding.md for
+// the encoding of the blockchain types.
+//
+// Structs and enums implement the traits with `#[derive(Encode, Decode)]`,
+// see the zgc-macros crate for the supported attributes. Compact formats
+// are in the `varint` and `rlp` submodules.
+
+pub mod rlp;
+pub mod varint;
+
+use crate::{Error, Hash};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+pub use zgc_macros::{Decode, Encode};
+
+/// Types with a canonical binary encoding.
+pub trait Encode {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        // writing into a Vec never fails
+        self.encode(&mut bytes)
+            .expect("failed to encode into a Vec");
+        bytes
+    }
+}
+
+/// Types that can be decoded from their canonical binary encoding.
+pub trait Decode: Sized {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error>;
+
+    /// Decodes a value that spans the whole slice.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = bytes;
+        let value = Self::decode(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(Error::TrailingBytes(reader.len()));
+        }
+        Ok(value)
+    }
+}
+
+/// Reads exactly `N` bytes.
+pub fn read_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], Error> {
+    let mut bytes = [0_u8; N];
+    reader.read_exact(&mut bytes).map_err(Error::from)?;
+    Ok(bytes)
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => Self::UnexpectedEnd,
+            kind => Self::Io(kind),
+        }
+    }
+}
+
+macro_rules! impl_integer {
+    ($($integer:ty),*) => {
+        $(
+            impl Encode for $integer {
+                fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+            }
+
+            impl Decode for $integer {
+                fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+                    Ok(Self::from_le_bytes(read_array(reader)?))
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, u128);
+
+impl Encode for usize {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        // cast is fine because usize is at most 64 bits wide
+        (*self as u64).encode(writer)
+    }
+}
+
+impl Decode for usize {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        usize::try_from(u64::decode(reader)?).map_err(|_| Error::Overflow)
+    }
+}
+
+impl Encode for bool {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        u8::from(*self).encode(writer)
+    }
+}
+
+impl Decode for bool {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(Error::InvalidTag(tag)),
+        }
+    }
+}
+
+impl<const N: usize> Encode for Hash<N> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.as_bytes())
+    }
+}
+
+impl<const N: usize> Decode for Hash<N> {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Ok(Self::new(read_array(reader)?))
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            None => 0_u8.encode(writer),
+            Some(value) => {
+                1_u8.encode(writer)?;
+                value.encode(writer)
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        match u8::decode(reader)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(reader)?)),
+            tag => Err(Error::InvalidTag(tag)),
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let len = u32::try_from(self.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many elements"))?;
+        len.encode(writer)?;
+        self.iter().try_for_each(|element| element.encode(writer))
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let len = u32::decode(reader)? as usize;
+        // the length is untrusted, so the memory is only reserved for a
+        // limited number of elements up front
+        let mut elements = Vec::with_capacity(len.min(1024));
+        for _ in 0..len {
+            elements.push(T::decode(reader)?);
+        }
+        Ok(elements)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Address, H256};
+
+    fn round_trip<T: Encode + Decode + PartialEq + std::fmt::Debug>(value: T, bytes: &[u8]) {
+        assert_eq!(value.to_bytes(), bytes);
+        assert_eq!(T::from_bytes(bytes), Ok(value));
+    }
+
+    #[test]
+    fn integers() {
+        round_trip(0xab_u8, &[0xab]);
+        round_trip(0x1234_u16, &[0x34, 0x12]);
+        round_trip(0x1234_5678_u32, &[0x78, 0x56, 0x34, 0x12]);
+        round_trip(1_u64, &[1, 0, 0, 0, 0, 0, 0, 0]);
+        round_trip(2_usize, &[2, 0, 0, 0, 0, 0, 0, 0]);
+        round_trip(u128::MAX, &[0xff; 16]);
+        round_trip(true, &[1]);
+        round_trip(false, &[0]);
+    }
+
+    #[test]
+    fn hashes_and_collections() {
+        let address = Address::new([7; 20]);
+        round_trip(address, &[7; 20]);
+
+        round_trip(Some(3_u8), &[1, 3]);
+        round_trip(None::<u8>, &[0]);
+        round_trip(
+            vec![H256::zero(); 2],
+            &[[2, 0, 0, 0].as_ref(), &[0; 64]].concat(),
+        );
+        round_trip(Vec::<u16>::new(), &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(u32::from_bytes(&[1, 2, 3]), Err(Error::UnexpectedEnd));
+        assert_eq!(u16::from_bytes(&[1, 2, 3]), Err(Error::TrailingBytes(1)));
+        assert_eq!(bool::from_bytes(&[2]), Err(Error::InvalidTag(2)));
+        assert_eq!(Option::<u8>::from_bytes(&[5, 0]), Err(Error::InvalidTag(5)));
+        assert_eq!(H256::from_bytes(&[0; 31]), Err(Error::UnexpectedEnd));
+
+        // a huge length prefix doesn't allocate memory up front
+        assert_eq!(
+            Vec::<u64>::from_bytes(&[0xff, 0xff, 0xff, 0xff, 1]),
+            Err(Error::UnexpectedEnd)
+        );
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[encoding(version = 3)]
+    struct Record {
+        #[encoding(order = 2)]
+        first: u8,
+        #[encoding(skip)]
+        cached: Option<u32>,
+        #[encoding(order = 1)]
+        second: Vec<u16>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    enum Message {
+        Ping,
+        Pair(u8, Option<Address>),
+        #[encoding(tag = 7)]
+        Nested {
+            record: Record,
+        },
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Wrapper<T>(T);
+
+    #[test]
+    fn derived_structs() {
+        let record = Record {
+            first: 9,
+            cached: None,
+            second: vec![0x0201],
+        };
+        round_trip(record, &[3, 1, 0, 0, 0, 1, 2, 9]);
+
+        // skipped fields are neither encoded nor decoded
+        let record = Record {
+            first: 0,
+            cached: Some(5),
+            second: Vec::new(),
+        };
+        assert_eq!(record.to_bytes(), [3, 0, 0, 0, 0, 0]);
+
+        assert_eq!(
+            Record::from_bytes(&[4, 0, 0, 0, 0, 0]),
+            Err(Error::UnsupportedVersion(4))
+        );
+        round_trip(Wrapper(0x0102_u16), &[2, 1]);
+    }
+
+    #[test]
+    fn derived_enums() {
+        round_trip(Message::Ping, &[0]);
+        round_trip(Message::Pair(5, None), &[1, 5, 0]);
+        round_trip(
+            Message::Nested {
+                record: Record {
+                    first: 1,
+                    cached: None,
+                    second: Vec::new(),
+                },
+            },
+            &[7, 3, 0, 0, 0, 0, 1],
+        );
+        assert_eq!(Message::from_bytes(&[2]), Err(Error::InvalidTag(2)));
+    }
+}
@@ -0,0 +1,456 @@
+// Recursive Length Prefix encoding, as used by Ethereum.
+//
+// An item is either a byte string or a list of items. The first byte
+// determines the kind and the length of the payload:
+//
+// - 0x00..=0x7f: a single byte string that is its own encoding
+// - 0x80..=0xb7: a byte string of up to 55 bytes, the length is the first
+//   byte minus 0x80
+// - 0xb8..=0xbf: a longer byte string, the first byte minus 0xb7 is the
+//   length of the big endian length that follows
+// - 0xc0..=0xf7 and 0xf8..=0xff: the same for lists, with the offsets 0xc0
+//   and 0xf7
+//
+// Integers are byte strings in big endian without leading zeros. Decoding
+// only accepts the shortest encoding of an item, and lists nested at most
+// `MAX_DEPTH` levels deep.
+
+use super::{Decode, Encode};
+use crate::{Error, Hash, U256};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+const STRING_OFFSET: u8 = 0x80;
+const LIST_OFFSET: u8 = 0xc0;
+/// The longest payload with the length in the first byte.
+const SHORT_LENGTH: usize = 55;
+
+/// The maximal nesting depth of lists accepted by decoding.
+pub const MAX_DEPTH: usize = 64;
+
+/// An RLP item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Bytes(Vec<u8>),
+    List(Vec<Item>),
+}
+
+impl Item {
+    pub fn as_bytes(&self) -> Result<&[u8], Error> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes),
+            Self::List(_) => Err(Error::UnexpectedList),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[Item], Error> {
+        match self {
+            Self::Bytes(_) => Err(Error::UnexpectedBytes),
+            Self::List(items) => Ok(items),
+        }
+    }
+
+    /// The length of the encoding of the item.
+    pub fn encoded_len(&self) -> usize {
+        let payload = self.payload_len();
+        match self {
+            Self::Bytes(bytes) if bytes.len() == 1 && bytes[0] < STRING_OFFSET => 1,
+            _ if payload > SHORT_LENGTH => 1 + be_len(payload) + payload,
+            _ => 1 + payload,
+        }
+    }
+
+    fn payload_len(&self) -> usize {
+        match self {
+            Self::Bytes(bytes) => bytes.len(),
+            Self::List(items) => items.iter().map(Item::encoded_len).sum(),
+        }
+    }
+}
+
+/// The number of bytes of `value` in big endian without leading zeros.
+fn be_len(value: usize) -> usize {
+    // cast is fine because the result is at most 8
+    (std::mem::size_of::<usize>() - value.leading_zeros() as usize / 8).max(1)
+}
+
+fn write_header<W: Write>(writer: &mut W, offset: u8, len: usize) -> io::Result<()> {
+    // casts are fine because the values are checked to fit
+    if len <= SHORT_LENGTH {
+        writer.write_all(&[offset + len as u8])
+    } else {
+        let bytes = (len as u64).to_be_bytes();
+        let len_len = be_len(len);
+        writer.write_all(&[offset + SHORT_LENGTH as u8 + len_len as u8])?;
+        writer.write_all(&bytes[8 - len_len..])
+    }
+}
+
+/// Reads the big endian length of a long item.
+fn read_long_len<R: Read>(reader: &mut R, len_len: u8) -> Result<usize, Error> {
+    let mut bytes = [0_u8; 8];
+    reader.read_exact(&mut bytes[8 - len_len as usize..])?;
+
+    // leading zeros and lengths that fit into the first byte have shorter
+    // encodings
+    if bytes[8 - len_len as usize] == 0 {
+        return Err(Error::NonCanonical);
+    }
+    let len = usize::try_from(u64::from_be_bytes(bytes)).map_err(|_| Error::Overflow)?;
+    if len <= SHORT_LENGTH {
+        return Err(Error::NonCanonical);
+    }
+    Ok(len)
+}
+
+/// The kind and payload length of an encoded item.
+enum Header {
+    /// A single byte below 0x80, which has no payload.
+    Byte(u8),
+    Bytes(usize),
+    List(usize),
+}
+
+fn read_header<R: Read>(reader: &mut R) -> Result<Header, Error> {
+    const LONG_STRING: u8 = STRING_OFFSET + SHORT_LENGTH as u8 + 1;
+    const LONG_LIST: u8 = LIST_OFFSET + SHORT_LENGTH as u8 + 1;
+
+    let first = u8::decode(reader)?;
+    Ok(match first {
+        0..=0x7f => Header::Byte(first),
+        STRING_OFFSET..=0xb7 => Header::Bytes(usize::from(first - STRING_OFFSET)),
+        LONG_STRING..=0xbf => Header::Bytes(read_long_len(reader, first - LONG_STRING + 1)?),
+        LIST_OFFSET..=0xf7 => Header::List(usize::from(first - LIST_OFFSET)),
+        LONG_LIST..=0xff => Header::List(read_long_len(reader, first - LONG_LIST + 1)?),
+    })
+}
+
+/// Single bytes below 0x80 are their own encoding.
+fn check_bytes(payload: &[u8]) -> Result<(), Error> {
+    if payload.len() == 1 && payload[0] < STRING_OFFSET {
+        return Err(Error::NonCanonical);
+    }
+    Ok(())
+}
+
+/// Splits the first `len` bytes off `bytes`.
+fn split_payload<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if bytes.len() < len {
+        return Err(Error::UnexpectedEnd);
+    }
+    let (payload, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(payload)
+}
+
+/// Reads exactly `len` bytes without trusting `len` for the allocation.
+fn read_payload<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>, Error> {
+    let mut payload = Vec::with_capacity(len.min(1024));
+    // cast is fine because usize is at most 64 bits wide
+    reader.take(len as u64).read_to_end(&mut payload)?;
+    if payload.len() != len {
+        return Err(Error::UnexpectedEnd);
+    }
+    Ok(payload)
+}
+
+impl Encode for Item {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::Bytes(bytes) if bytes.len() == 1 && bytes[0] < STRING_OFFSET => {
+                writer.write_all(bytes)
+            }
+            Self::Bytes(bytes) => {
+                write_header(writer, STRING_OFFSET, bytes.len())?;
+                writer.write_all(bytes)
+            }
+            Self::List(items) => {
+                write_header(writer, LIST_OFFSET, self.payload_len())?;
+                items.iter().try_for_each(|item| item.encode(writer))
+            }
+        }
+    }
+}
+
+impl Item {
+    /// Decodes an item from the front of `bytes`, inside `depth` lists.
+    fn decode_slice(bytes: &mut &[u8], depth: usize) -> Result<Self, Error> {
+        match read_header(bytes)? {
+            Header::Byte(byte) => Ok(Self::Bytes(vec![byte])),
+            Header::Bytes(len) => {
+                let payload = split_payload(bytes, len)?;
+                check_bytes(payload)?;
+                Ok(Self::Bytes(payload.to_vec()))
+            }
+            Header::List(len) => Self::decode_list(split_payload(bytes, len)?, depth),
+        }
+    }
+
+    /// Decodes the payload of a list inside `depth` lists.
+    fn decode_list(mut payload: &[u8], depth: usize) -> Result<Self, Error> {
+        if depth >= MAX_DEPTH {
+            return Err(Error::TooDeep(MAX_DEPTH));
+        }
+        let mut items = Vec::new();
+        while !payload.is_empty() {
+            items.push(Self::decode_slice(&mut payload, depth + 1)?);
+        }
+        Ok(Self::List(items))
+    }
+}
+
+impl Decode for Item {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        match read_header(reader)? {
+            Header::Byte(byte) => Ok(Self::Bytes(vec![byte])),
+            Header::Bytes(len) => {
+                let payload = read_payload(reader, len)?;
+                check_bytes(&payload)?;
+                Ok(Self::Bytes(payload))
+            }
+            Header::List(len) => Self::decode_list(&read_payload(reader, len)?, 0),
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = bytes;
+        let item = Self::decode_slice(&mut reader, 0)?;
+        if !reader.is_empty() {
+            return Err(Error::TrailingBytes(reader.len()));
+        }
+        Ok(item)
+    }
+}
+
+impl From<Vec<u8>> for Item {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl From<Vec<Item>> for Item {
+    fn from(items: Vec<Item>) -> Self {
+        Self::List(items)
+    }
+}
+
+impl<const N: usize> From<Hash<N>> for Item {
+    fn from(hash: Hash<N>) -> Self {
+        Self::Bytes(hash.as_bytes().to_vec())
+    }
+}
+
+impl<const N: usize> TryFrom<&Item> for Hash<N> {
+    type Error = Error;
+
+    fn try_from(item: &Item) -> Result<Self, Self::Error> {
+        Self::try_from(item.as_bytes()?)
+    }
+}
+
+/// Strips the leading zeros of a big endian integer.
+fn trim_integer(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes
+        .iter()
+        .position(|&byte| byte != 0)
+        .unwrap_or(bytes.len());
+    bytes[start..].to_vec()
+}
+
+/// The big endian integer of an item, padded to `N` bytes.
+fn integer<const N: usize>(item: &Item) -> Result<[u8; N], Error> {
+    let bytes = item.as_bytes()?;
+    if bytes.first() == Some(&0) {
+        return Err(Error::NonCanonical);
+    }
+    if bytes.len() > N {
+        return Err(Error::Overflow);
+    }
+
+    let mut padded = [0_u8; N];
+    padded[N - bytes.len()..].copy_from_slice(bytes);
+    Ok(padded)
+}
+
+impl From<u64> for Item {
+    fn from(value: u64) -> Self {
+        Self::Bytes(trim_integer(&value.to_be_bytes()))
+    }
+}
+
+impl TryFrom<&Item> for u64 {
+    type Error = Error;
+
+    fn try_from(item: &Item) -> Result<Self, Self::Error> {
+        integer(item).map(u64::from_be_bytes)
+    }
+}
+
+impl From<U256> for Item {
+    fn from(value: U256) -> Self {
+        Self::Bytes(trim_integer(&value.to_be_bytes()))
+    }
+}
+
+impl TryFrom<&Item> for U256 {
+    type Error = Error;
+
+    fn try_from(item: &Item) -> Result<Self, Self::Error> {
+        integer(item).map(U256::from_be_bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::H256;
+
+    fn bytes(string: &str) -> Item {
+        Item::Bytes(string.as_bytes().to_vec())
+    }
+
+    fn round_trip(item: Item, encoding: &[u8]) {
+        assert_eq!(item.to_bytes(), encoding);
+        assert_eq!(item.encoded_len(), encoding.len());
+        assert_eq!(Item::from_bytes(encoding), Ok(item));
+    }
+
+    #[test]
+    fn examples() {
+        // the examples of the Ethereum yellow paper and wiki
+        round_trip(bytes("dog"), &[0x83, b'd', b'o', b'g']);
+        round_trip(
+            Item::List(vec![bytes("cat"), bytes("dog")]),
+            &[0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g'],
+        );
+        round_trip(bytes(""), &[0x80]);
+        round_trip(Item::List(Vec::new()), &[0xc0]);
+        round_trip(Item::from(0), &[0x80]);
+        round_trip(Item::from(15), &[0x0f]);
+        round_trip(Item::from(1024), &[0x82, 0x04, 0x00]);
+        round_trip(
+            Item::List(vec![
+                Item::List(Vec::new()),
+                Item::List(vec![Item::List(Vec::new())]),
+                Item::List(vec![
+                    Item::List(Vec::new()),
+                    Item::List(vec![Item::List(Vec::new())]),
+                ]),
+            ]),
+            &[0xc7, 0xc0, 0xc1, 0xc0, 0xc3, 0xc0, 0xc1, 0xc0],
+        );
+
+        let lorem = "Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        let mut encoding = vec![0xb8, 0x38];
+        encoding.extend_from_slice(lorem.as_bytes());
+        round_trip(bytes(lorem), &encoding);
+    }
+
+    #[test]
+    fn long_list() {
+        let item = Item::List(vec![Item::from(vec![0xaa; 300])]);
+        let mut encoding = vec![0xf9, 0x01, 0x2f, 0xb9, 0x01, 0x2c];
+        encoding.extend_from_slice(&[0xaa; 300]);
+        round_trip(item, &encoding);
+    }
+
+    #[test]
+    fn conversions() {
+        let hash = H256::new([0x11; 32]);
+        let item = Item::from(hash);
+        assert_eq!(H256::try_from(&item), Ok(hash));
+        assert_eq!(
+            crate::Address::try_from(&item),
+            Err(Error::InvalidLength {
+                expected: 20,
+                found: 32
+            })
+        );
+
+        assert_eq!(u64::try_from(&Item::from(u64::MAX)), Ok(u64::MAX));
+        assert_eq!(U256::try_from(&Item::from(U256::MAX)), Ok(U256::MAX));
+        assert_eq!(u64::try_from(&Item::from(U256::MAX)), Err(Error::Overflow));
+        assert_eq!(
+            u64::try_from(&Item::Bytes(vec![0, 1])),
+            Err(Error::NonCanonical)
+        );
+        assert_eq!(
+            u64::try_from(&Item::List(Vec::new())),
+            Err(Error::UnexpectedList)
+        );
+        assert_eq!(bytes("").as_list(), Err(Error::UnexpectedBytes));
+    }
+
+    #[test]
+    fn malformed_input() {
+        // single bytes below 0x80 are their own encoding
+        assert_eq!(Item::from_bytes(&[0x81, 0x05]), Err(Error::NonCanonical));
+        // short payloads have the length in the first byte
+        assert_eq!(
+            Item::from_bytes(&[0xb8, 0x01, 0xaa]),
+            Err(Error::NonCanonical)
+        );
+        // leading zeros in the length
+        assert_eq!(
+            Item::from_bytes(&[0xb9, 0x00, 0x38]),
+            Err(Error::NonCanonical)
+        );
+
+        // truncated items and lists
+        assert_eq!(
+            Item::from_bytes(&[0x83, b'd', b'o']),
+            Err(Error::UnexpectedEnd)
+        );
+        assert_eq!(Item::from_bytes(&[0xb9, 0x01]), Err(Error::UnexpectedEnd));
+        assert_eq!(
+            Item::from_bytes(&[0xc2, 0x83, b'd']),
+            Err(Error::UnexpectedEnd)
+        );
+        assert_eq!(Item::from_bytes(&[]), Err(Error::UnexpectedEnd));
+        assert_eq!(
+            Item::from_bytes(&[0x05, 0x05]),
+            Err(Error::TrailingBytes(1))
+        );
+
+        // a huge length doesn't allocate memory up front
+        assert_eq!(
+            Item::from_bytes(&[0xbf, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+            Err(Error::UnexpectedEnd)
+        );
+    }
+
+    /// `depth` lists nested in each other.
+    fn nested(depth: usize) -> Item {
+        (0..depth).fold(Item::List(Vec::new()), |item, _| Item::List(vec![item]))
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let item = nested(MAX_DEPTH - 1);
+        assert_eq!(Item::from_bytes(&item.to_bytes()), Ok(item.clone()));
+        assert_eq!(Item::decode(&mut item.to_bytes().as_slice()), Ok(item));
+
+        let too_deep = nested(MAX_DEPTH).to_bytes();
+        assert_eq!(Item::from_bytes(&too_deep), Err(Error::TooDeep(MAX_DEPTH)));
+        assert_eq!(
+            Item::decode(&mut too_deep.as_slice()),
+            Err(Error::TooDeep(MAX_DEPTH))
+        );
+
+        // far deeper nesting is rejected without overflowing the stack, the
+        // headers are built from the innermost list outwards
+        let mut headers = Vec::new();
+        let mut len = 0;
+        for _ in 0..100_000 {
+            let mut header = Vec::new();
+            write_header(&mut header, LIST_OFFSET, len).unwrap();
+            len += header.len();
+            headers.push(header);
+        }
+        let encoding: Vec<u8> = headers.into_iter().rev().flatten().collect();
+        assert_eq!(
+            Item::from_bytes(&encoding).unwrap_err(),
+            Error::TooDeep(MAX_DEPTH)
+        );
+    }
+}
@@ -0,0 +1,196 @@
+// Variable-length integers.
+//
+// CompactSize is the length prefix format of Bitcoin: values below 0xfd
+// are a single byte, larger values are a marker byte (0xfd, 0xfe or 0xff)
+// followed by the value as little endian u16, u32 or u64. LEB128 stores 7
+// bits per byte, least significant group first, with the high bit set on
+// every byte but the last.
+//
+// Both formats allow several encodings of the same value, the decoders
+// only accept the shortest one.
+
+use super::{Decode, Encode};
+use crate::Error;
+use std::io::{self, Read, Write};
+
+/// A `u64` encoded as CompactSize.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompactSize(pub u64);
+
+/// A `u64` encoded as unsigned LEB128.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Leb128(pub u64);
+
+/// The longest LEB128 encoding of a `u64`.
+const LEB128_MAX_LEN: usize = 10;
+
+pub fn write_compact_size<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+    // casts are fine because the value is checked to fit
+    match value {
+        0..=0xfc => (value as u8).encode(writer),
+        0xfd..=0xffff => {
+            0xfd_u8.encode(writer)?;
+            (value as u16).encode(writer)
+        }
+        0x1_0000..=0xffff_ffff => {
+            0xfe_u8.encode(writer)?;
+            (value as u32).encode(writer)
+        }
+        _ => {
+            0xff_u8.encode(writer)?;
+            value.encode(writer)
+        }
+    }
+}
+
+pub fn read_compact_size<R: Read>(reader: &mut R) -> Result<u64, Error> {
+    let (value, minimum) = match u8::decode(reader)? {
+        0xfd => (u64::from(u16::decode(reader)?), 0xfd),
+        0xfe => (u64::from(u32::decode(reader)?), 0x1_0000),
+        0xff => (u64::decode(reader)?, 0x1_0000_0000),
+        value => return Ok(u64::from(value)),
+    };
+
+    if value < minimum {
+        return Err(Error::NonCanonical);
+    }
+    Ok(value)
+}
+
+pub fn write_leb128<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        // cast is fine because the value is masked to 7 bits
+        let group = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            return group.encode(writer);
+        }
+        (group | 0x80).encode(writer)?;
+    }
+}
+
+pub fn read_leb128<R: Read>(reader: &mut R) -> Result<u64, Error> {
+    let mut value = 0_u64;
+
+    for i in 0..LEB128_MAX_LEN {
+        let byte = u8::decode(reader)?;
+        let group = u64::from(byte & 0x7f);
+
+        // the 10th byte holds only the highest bit of a u64
+        if i == LEB128_MAX_LEN - 1 && group > 1 {
+            return Err(Error::Overflow);
+        }
+        value |= group << (7 * i);
+
+        if byte & 0x80 == 0 {
+            // a trailing zero group could have been left out
+            if i > 0 && group == 0 {
+                return Err(Error::NonCanonical);
+            }
+            return Ok(value);
+        }
+    }
+
+    Err(Error::Overflow)
+}
+
+impl Encode for CompactSize {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_compact_size(writer, self.0)
+    }
+}
+
+impl Decode for CompactSize {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        read_compact_size(reader).map(Self)
+    }
+}
+
+impl Encode for Leb128 {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_leb128(writer, self.0)
+    }
+}
+
+impl Decode for Leb128 {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        read_leb128(reader).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoding::{Decode, Encode};
+
+    #[test]
+    fn compact_size() {
+        let cases: &[(u64, &[u8])] = &[
+            (0, &[0]),
+            (0xfc, &[0xfc]),
+            (0xfd, &[0xfd, 0xfd, 0]),
+            (0xffff, &[0xfd, 0xff, 0xff]),
+            (0x1_0000, &[0xfe, 0, 0, 1, 0]),
+            (0x1_0000_0000, &[0xff, 0, 0, 0, 0, 1, 0, 0, 0]),
+            (u64::MAX, &[0xff; 9]),
+        ];
+
+        for (value, bytes) in cases {
+            assert_eq!(CompactSize(*value).to_bytes(), *bytes);
+            assert_eq!(CompactSize::from_bytes(bytes), Ok(CompactSize(*value)));
+        }
+    }
+
+    #[test]
+    fn leb128() {
+        let cases: &[(u64, &[u8])] = &[
+            (0, &[0]),
+            (0x7f, &[0x7f]),
+            (0x80, &[0x80, 0x01]),
+            (624_485, &[0xe5, 0x8e, 0x26]),
+            (
+                u64::MAX,
+                &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            ),
+        ];
+
+        for (value, bytes) in cases {
+            assert_eq!(Leb128(*value).to_bytes(), *bytes);
+            assert_eq!(Leb128::from_bytes(bytes), Ok(Leb128(*value)));
+        }
+    }
+
+    #[test]
+    fn malformed_input() {
+        // shorter encodings exist
+        assert_eq!(
+            CompactSize::from_bytes(&[0xfd, 0xfc, 0]),
+            Err(Error::NonCanonical)
+        );
+        assert_eq!(
+            CompactSize::from_bytes(&[0xfe, 0xff, 0xff, 0, 0]),
+            Err(Error::NonCanonical)
+        );
+        assert_eq!(Leb128::from_bytes(&[0x80, 0x00]), Err(Error::NonCanonical));
+        assert_eq!(
+            Leb128::from_bytes(&[0xff, 0x80, 0x00]),
+            Err(Error::NonCanonical)
+        );
+
+        // more than 64 bits
+        assert_eq!(
+            Leb128::from_bytes(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]),
+            Err(Error::Overflow)
+        );
+        assert_eq!(Leb128::from_bytes(&[0x80; 11]), Err(Error::Overflow));
+
+        // truncated
+        assert_eq!(
+            CompactSize::from_bytes(&[0xfe, 0, 0]),
+            Err(Error::UnexpectedEnd)
+        );
+        assert_eq!(Leb128::from_bytes(&[0x80]), Err(Error::UnexpectedEnd));
+        assert_eq!(Leb128::from_bytes(&[]), Err(Error::UnexpectedEnd));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::Network;
+use std::{fmt, io};
+
+/// The alphabet of a textual encoding, used to describe invalid characters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Alphabet {
+    /// Hex digits in either case.
+    Hex,
+    /// The digits 0 to 9.
+    Decimal,
+    /// The Bitcoin base58 alphabet, without 0, O, I and l.
+    Base58,
+    /// The standard base64 alphabet with `+` and `/`.
+    Base64,
+    /// The bech32 data characters.
+    Bech32,
+}
+
+impl fmt::Display for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Hex => "hex",
+            Self::Decimal => "decimal",
+            Self::Base58 => "base58",
+            Self::Base64 => "base64",
+            Self::Bech32 => "bech32",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Errors of parsing and decoding the common types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The input has the wrong length, e.g. a hash with too few hex digits.
+    InvalidLength { expected: usize, found: usize },
+    /// The input is shorter than the minimal length.
+    TooShort { minimum: usize, found: usize },
+    /// The input is longer than the maximal length.
+    TooLong { maximum: usize, found: usize },
+    /// A character is not in the alphabet, `position` counts characters.
+    InvalidCharacter {
+        alphabet: Alphabet,
+        character: char,
+        position: usize,
+    },
+    /// The padding (or the unused bits of the last character) is malformed.
+    InvalidPadding(Alphabet),
+    /// A checksummed hex string has the wrong letter case, see
+    /// `zgc_crypto::address::ChecksumAddress`.
+    InvalidChecksumCase {
+        position: usize,
+        expected: char,
+        found: char,
+    },
+    /// The checksum of a bech32 string matches neither variant.
+    InvalidBech32Checksum,
+    /// A bech32 string contains both lowercase and uppercase characters.
+    MixedCase,
+    /// A bech32 string has no `1` between the prefix and the data.
+    MissingSeparator,
+    /// The prefix of a bech32 string is empty, too long or not printable ASCII.
+    InvalidHumanReadablePart(String),
+    /// The input is valid, but encodes a value of another network.
+    WrongNetwork { expected: Network, found: Network },
+    /// A number does not fit into its type.
+    Overflow,
+    /// A number was parsed from an empty string.
+    Empty,
+    /// Numbers are only parsed in radix 10 or 16.
+    UnsupportedRadix(u32),
+    /// The unit of an amount is not a known denomination.
+    UnknownDenomination(String),
+    /// The binary encoding ended in the middle of a value.
+    UnexpectedEnd,
+    /// Bytes are left over after decoding a value.
+    TrailingBytes(usize),
+    /// An enum variant or option tag of the binary encoding is unknown.
+    InvalidTag(u8),
+    /// The version of a versioned binary encoding is unknown.
+    UnsupportedVersion(u8),
+    /// The value has a shorter encoding, e.g. a varint with superfluous
+    /// continuation bytes.
+    NonCanonical,
+    /// An RLP list was found where a byte string was expected.
+    UnexpectedList,
+    /// An RLP byte string was found where a list was expected.
+    UnexpectedBytes,
+    /// RLP lists are nested deeper than the given maximal depth.
+    TooDeep(usize),
+    /// Reading the binary encoding failed.
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidLength { expected, found } => {
+                write!(f, "input length was {}, expected {}", found, expected)
+            }
+            Self::TooShort { minimum, found } => write!(
+                f,
+                "input length was {}, expected at least {}",
+                found, minimum
+            ),
+            Self::TooLong { maximum, found } => write!(
+                f,
+                "input length was {}, expected at most {}",
+                found, maximum
+            ),
+            Self::InvalidCharacter {
+                alphabet,
+                character,
+                position,
+            } => write!(
+                f,
+                "invalid {} character {:?} at position {}",
+                alphabet, character, position
+            ),
+            Self::InvalidPadding(alphabet) => write!(f, "invalid {} padding", alphabet),
+            Self::InvalidChecksumCase {
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "invalid checksum at position {}: expected {:?}, found {:?}",
+                position, expected, found
+            ),
+            Self::InvalidBech32Checksum => f.write_str("invalid bech32 checksum"),
+            Self::MixedCase => {
+                f.write_str("input contains both lowercase and uppercase characters")
+            }
+            Self::MissingSeparator => f.write_str("missing separator '1'"),
+            Self::InvalidHumanReadablePart(hrp) => {
+                write!(f, "invalid human-readable part {:?}", hrp)
+            }
+            Self::WrongNetwork { expected, found } => {
+                write!(f, "input belongs to {}, expected {}", found, expected)
+            }
+            Self::Overflow => f.write_str("number too large to fit in target type"),
+            Self::Empty => f.write_str("cannot parse from an empty string"),
+            Self::UnsupportedRadix(radix) => {
+                write!(f, "radix was {}, expected 10 or 16", radix)
+            }
+            Self::UnknownDenomination(symbol) => write!(f, "unknown denomination {:?}", symbol),
+            Self::UnexpectedEnd => f.write_str("unexpected end of input"),
+            Self::TrailingBytes(count) => write!(f, "{} trailing bytes after the value", count),
+            Self::InvalidTag(tag) => write!(f, "invalid tag 0x{:02x}", tag),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported encoding version {}", version)
+            }
+            Self::NonCanonical => f.write_str("non-canonical encoding"),
+            Self::UnexpectedList => f.write_str("expected a byte string, found a list"),
+            Self::UnexpectedBytes => f.write_str("expected a list, found a byte string"),
+            Self::TooDeep(depth) => write!(f, "lists nested deeper than {} levels", depth),
+            Self::Io(kind) => write!(f, "cannot read input: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,4 +1,26 @@
+// lets the derive macros refer to this crate as `zgc_common` from inside it
+extern crate self as zgc_common;
+
+mod amount;
+pub mod base58;
+pub mod base64;
+pub mod bech32;
+pub mod encoding;
+mod error;
+mod network;
+mod time;
 mod types;
+mod uint;
+
+pub use amount::{Amount, Denomination};
+pub use error::{Alphabet, Error};
+pub use network::Network;
+pub use time::{Clock, MockClock, SystemClock, Timestamp};
+pub use types::Hash;
+pub use uint::U256;
+pub use zgc_macros::{address, hash};
 
-pub type Address = types::Hash<20>;
-pub type H256 = types::Hash<32>;
+pub type Address = Hash<20>;
+pub type H256 = Hash<32>;
+pub type PublicKey = Hash<33>;
+pub type Signature = Hash<64>;
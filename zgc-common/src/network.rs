@@ -0,0 +1,18 @@
+/// The network that keys and addresses belong to.
+///
+/// Encodings that contain a network marker (e.g. a version byte) make sure
+/// that a testnet key or address is never mistaken for a mainnet one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Mainnet => write!(f, "mainnet"),
+            Self::Testnet => write!(f, "testnet"),
+        }
+    }
+}
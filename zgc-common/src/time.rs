@@ -0,0 +1,273 @@
+use crate::encoding::{Decode, Encode};
+use crate::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A point in time as milliseconds since the unix epoch.
+///
+/// The textual form is RFC 3339 in UTC, e.g. `2020-09-13T12:26:40Z`, with
+/// milliseconds only if they are not zero.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, std::hash::Hash)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub const UNIX_EPOCH: Self = Self(0);
+
+    pub const fn from_unix_millis(millis: u64) -> Self {
+        Self(millis)
+    }
+
+    pub fn from_unix_secs(secs: u64) -> Result<Self, Error> {
+        secs.checked_mul(1000).map(Self).ok_or(Error::Overflow)
+    }
+
+    pub const fn as_unix_millis(self) -> u64 {
+        self.0
+    }
+
+    pub const fn as_unix_secs(self) -> u64 {
+        self.0 / 1000
+    }
+
+    pub fn checked_add(self, duration: Duration) -> Option<Self> {
+        u64::try_from(duration.as_millis())
+            .ok()
+            .and_then(|millis| self.0.checked_add(millis))
+            .map(Self)
+    }
+
+    pub fn checked_sub(self, duration: Duration) -> Option<Self> {
+        u64::try_from(duration.as_millis())
+            .ok()
+            .and_then(|millis| self.0.checked_sub(millis))
+            .map(Self)
+    }
+
+    /// The time elapsed from `earlier` to this timestamp, or `None` if
+    /// `earlier` is later.
+    pub fn duration_since(self, earlier: Self) -> Option<Duration> {
+        self.0.checked_sub(earlier.0).map(Duration::from_millis)
+    }
+}
+
+/// Fails for times before the unix epoch or too far in the future.
+impl TryFrom<SystemTime> for Timestamp {
+    type Error = Error;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        let since_epoch = time
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| Error::Overflow)?;
+        u64::try_from(since_epoch.as_millis())
+            .map(Self)
+            .map_err(|_| Error::Overflow)
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        UNIX_EPOCH + Duration::from_millis(timestamp.0)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.0 / 1000;
+        let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+        // civil date of the days since the epoch, see
+        // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let day_of_era = z % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            secs_of_day / 3600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60
+        )?;
+        let millis = self.0 % 1000;
+        if millis != 0 {
+            write!(f, ".{:03}", millis)?;
+        }
+        f.write_str("Z")
+    }
+}
+
+impl Encode for Timestamp {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.encode(writer)
+    }
+}
+
+impl Decode for Timestamp {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        u64::decode(reader).map(Self)
+    }
+}
+
+/// Serialized as the milliseconds since the epoch in every format.
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(Self)
+    }
+}
+
+/// A source of the current time.
+///
+/// Everything that timestamps or validates blocks takes a clock instead of
+/// reading the system time, so tests can use a [`MockClock`].
+pub trait Clock {
+    fn now(&self) -> Timestamp;
+}
+
+/// The system time.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::try_from(SystemTime::now()).expect("system time is before the unix epoch")
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Debug, Default)]
+pub struct MockClock(AtomicU64);
+
+impl MockClock {
+    pub fn new(now: Timestamp) -> Self {
+        Self(AtomicU64::new(now.0))
+    }
+
+    pub fn set(&self, now: Timestamp) {
+        self.0.store(now.0, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let now = self
+            .now()
+            .checked_add(duration)
+            .expect("mock clock overflowed");
+        self.set(now);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Timestamp {
+        Timestamp(self.0.load(Ordering::SeqCst))
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Timestamp {
+        (**self).now()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let timestamp = Timestamp::from_unix_secs(1_600_000_000).unwrap();
+        assert_eq!(timestamp.as_unix_millis(), 1_600_000_000_000);
+        assert_eq!(timestamp.as_unix_secs(), 1_600_000_000);
+        assert_eq!(Timestamp::from_unix_secs(u64::MAX), Err(Error::Overflow));
+
+        let later = timestamp.checked_add(Duration::from_millis(1500)).unwrap();
+        assert_eq!(
+            later.duration_since(timestamp),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(timestamp.duration_since(later), None);
+        assert_eq!(
+            later.checked_sub(Duration::from_millis(1500)),
+            Some(timestamp)
+        );
+        assert_eq!(
+            Timestamp::UNIX_EPOCH.checked_sub(Duration::from_millis(1)),
+            None
+        );
+
+        let system_time = SystemTime::from(later);
+        assert_eq!(Timestamp::try_from(system_time), Ok(later));
+        assert_eq!(
+            Timestamp::try_from(UNIX_EPOCH - Duration::from_secs(1)),
+            Err(Error::Overflow)
+        );
+    }
+
+    #[test]
+    fn rfc3339() {
+        assert_eq!(Timestamp::UNIX_EPOCH.to_string(), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            Timestamp::from_unix_secs(1_600_000_000)
+                .unwrap()
+                .to_string(),
+            "2020-09-13T12:26:40Z"
+        );
+        assert_eq!(
+            Timestamp::from_unix_millis(951_782_400_123).to_string(),
+            "2000-02-29T00:00:00.123Z"
+        );
+        assert_eq!(
+            Timestamp::from_unix_secs(253_402_300_799)
+                .unwrap()
+                .to_string(),
+            "9999-12-31T23:59:59Z"
+        );
+    }
+
+    #[test]
+    fn clocks() {
+        let start = Timestamp::from_unix_secs(1_600_000_000).unwrap();
+        let clock = MockClock::new(start);
+        assert_eq!(clock.now(), start);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(
+            clock.now().duration_since(start),
+            Some(Duration::from_secs(60))
+        );
+
+        let by_reference: &dyn Clock = &clock;
+        assert_eq!((&by_reference).now(), clock.now());
+
+        assert!(SystemClock.now() > start);
+    }
+
+    #[test]
+    fn encoding() {
+        let timestamp = Timestamp::from_unix_millis(0x0102);
+        assert_eq!(timestamp.to_bytes(), [2, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(Timestamp::from_bytes(&timestamp.to_bytes()), Ok(timestamp));
+        assert_eq!(serde_json::to_string(&timestamp).unwrap(), "258");
+    }
+}
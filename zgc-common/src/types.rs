@@ -1,70 +1,178 @@
-use serde::de::Visitor;
+use crate::base64;
+use crate::error::{Alphabet, Error};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::convert::TryFrom;
 use std::convert::TryInto;
+use std::fmt;
+use std::ops::Index;
+use std::slice::SliceIndex;
+use std::str::FromStr;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// A fixed size byte array, e.g. a hash, an address or a public key.
+///
+/// The textual form is lowercase hex, `{:#}` prepends `0x`. Ordering
+/// compares the bytes lexicographically.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, std::hash::Hash)]
 pub struct Hash<const N: usize>([u8; N]);
 
 impl<const N: usize> Hash<N> {
-    pub fn try_from_str(string: &str) -> Result<Self, String> {
+    pub fn try_from_str(string: &str) -> Result<Self, Error> {
         let trimmed_string = string.trim_start_matches("0x");
 
         if trimmed_string.len() != 2 * N {
-            return Err(format!(
-                "input length was {}, expected {}",
-                trimmed_string.len(),
-                2 * N
-            ));
+            return Err(Error::InvalidLength {
+                expected: 2 * N,
+                found: trimmed_string.len(),
+            });
         }
 
         let mut array = [0_u8; N];
 
-        array
-            .iter_mut()
-            .enumerate()
-            .try_for_each(|(i, byte)| -> Result<(), String> {
-                let str_window = &trimmed_string[2 * i..2 * i + 2];
-                let parsed_byte = u8::from_str_radix(str_window, 16)
-                    .map_err(|e| format!("cannot parse into hexadecimal: {}", e))?;
-
-                *byte = parsed_byte;
-                Ok(())
+        // hex digits are ASCII, so if every character is a hex digit there
+        // are exactly 2 * N of them
+        for (position, character) in trimmed_string.chars().enumerate() {
+            let digit = character.to_digit(16).ok_or(Error::InvalidCharacter {
+                alphabet: Alphabet::Hex,
+                character,
+                position,
             })?;
 
+            // cast is fine because a hex digit is less than 16
+            array[position / 2] |= (digit as u8) << (4 * (1 - position % 2));
+        }
+
         Ok(Self(array))
     }
 
-    pub fn to_string(&self) -> String {
-        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    pub const fn new(bytes: [u8; N]) -> Self {
+        Self(bytes)
     }
 
-    pub fn new(bytes: [u8; N]) -> Self {
-        Self(bytes)
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
     }
 
-    pub fn zero() -> Self {
+    pub const fn zero() -> Self {
         Self([0_u8; N])
     }
+
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.0)
+    }
+
+    pub fn try_from_base64(string: &str) -> Result<Self, Error> {
+        Self::try_from(base64::decode(string)?.as_slice())
+    }
+}
+
+impl<const N: usize> fmt::Display for Hash<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl<const N: usize> fmt::Debug for Hash<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Hash<{}>({:#x})", N, self)
+    }
+}
+
+impl<const N: usize> fmt::LowerHex for Hash<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl<const N: usize> fmt::UpperHex for Hash<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.0.iter().try_for_each(|byte| write!(f, "{:02X}", byte))
+    }
+}
+
+impl<const N: usize> FromStr for Hash<N> {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Self::try_from_str(string)
+    }
 }
 
+impl<const N: usize> TryFrom<&str> for Hash<N> {
+    type Error = Error;
+
+    fn try_from(string: &str) -> Result<Self, Self::Error> {
+        Self::try_from_str(string)
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Hash<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Hash<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> From<Hash<N>> for [u8; N] {
+    fn from(hash: Hash<N>) -> Self {
+        hash.0
+    }
+}
+
+impl<const N: usize, I: SliceIndex<[u8]>> Index<I> for Hash<N> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+/// Human-readable formats (e.g. JSON) get the hash as a hex string, binary
+/// formats (e.g. bincode, CBOR, postcard) get the N raw bytes as a tuple,
+/// which has no length prefix.
 impl<const N: usize> Serialize for Hash<N> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(&self.to_string())
-        //serializer.serialize_bytes(&self.0)
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            let mut tuple = serializer.serialize_tuple(N)?;
+            for byte in &self.0 {
+                tuple.serialize_element(byte)?;
+            }
+            tuple.end()
+        }
     }
 }
 
+/// Both hex strings and byte sequences are accepted regardless of the
+/// format, the format only hints which one to expect.
 impl<'de, const N: usize> Deserialize<'de> for Hash<N> {
     fn deserialize<D>(deserializer: D) -> Result<Hash<N>, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_str(HashVisitor::<N>)
-        //deserializer.deserialize_bytes(HashVisitor::<N>)
+        // human-readable formats are self-describing, so the hash can be
+        // either a string or a sequence of numbers
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(HashVisitor::<N>)
+        } else {
+            deserializer.deserialize_tuple(N, HashVisitor::<N>)
+        }
     }
 }
 
@@ -74,22 +182,38 @@ impl<'de, const N: usize> Visitor<'de> for HashVisitor<N> {
     type Value = Hash<N>;
 
     fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "a String slice")
+        write!(f, "a hex string or {} bytes", N)
     }
 
-    //fn visit_bytes<T: serde::de::Error>(self, value: &[u8]) -> Result<Self::Value, T> {
-    //    let result = Self::Value::try_from(value)
-    //        .map_err(|e| T::custom(format!("Deserialization error: {:?}", e)))?;
+    fn visit_bytes<T: de::Error>(self, value: &[u8]) -> Result<Self::Value, T> {
+        let result = Self::Value::try_from(value)
+            .map_err(|e| T::custom(format!("Deserialization error: {}", e)))?;
 
-    //    Ok(result)
-    //}
+        Ok(result)
+    }
 
-    fn visit_str<T: serde::de::Error>(self, value: &str) -> Result<Self::Value, T> {
+    fn visit_str<T: de::Error>(self, value: &str) -> Result<Self::Value, T> {
         let result = Self::Value::try_from_str(value)
-            .map_err(|e| T::custom(format!("Deserialization error: {:?}", e)))?;
+            .map_err(|e| T::custom(format!("Deserialization error: {}", e)))?;
 
         Ok(result)
     }
+
+    // some formats (e.g. JSON) represent bytes as a sequence of numbers
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut array = [0_u8; N];
+        for (i, byte) in array.iter_mut().enumerate() {
+            *byte = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(de::Error::invalid_length(N + 1, &self));
+        }
+
+        Ok(Hash(array))
+    }
 }
 
 impl<const N: usize> Default for Hash<N> {
@@ -99,12 +223,13 @@ impl<const N: usize> Default for Hash<N> {
 }
 
 impl<const N: usize> TryFrom<&[u8]> for Hash<N> {
-    type Error = String;
+    type Error = Error;
 
     fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        let result: [u8; N] = bytes
-            .try_into()
-            .map_err(|_| format!("input (&[u8]) has {}, expected {}", bytes.len(), N))?;
+        let result: [u8; N] = bytes.try_into().map_err(|_| Error::InvalidLength {
+            expected: N,
+            found: bytes.len(),
+        })?;
 
         Ok(Self(result))
     }
@@ -113,20 +238,107 @@ impl<const N: usize> TryFrom<&[u8]> for Hash<N> {
 #[cfg(test)]
 mod test {
     use super::Hash;
+    use crate::error::{Alphabet, Error};
+    use crate::{Address, H256};
+    use serde_test::{Configure, Readable};
+    use std::collections::{BTreeSet, HashMap};
+
+    /// The tokens of a tuple of `bytes`.
+    fn tuple_tokens(bytes: &[u8]) -> Vec<serde_test::Token> {
+        let mut tokens = vec![serde_test::Token::Tuple { len: bytes.len() }];
+        tokens.extend(bytes.iter().map(|&byte| serde_test::Token::U8(byte)));
+        tokens.push(serde_test::Token::TupleEnd);
+        tokens
+    }
 
     #[test]
     fn serde_tests() {
         let hash = Hash::<4>::new([0, 2, 0xfd, 0xa0]);
-        //let expected = "0002fda0";
-        //serde_test::assert_tokens(&hash, &[serde_test::Token::String(expected)]);
-        let expected = &[0, 2, 0xfd, 0xa0];
-        serde_test::assert_tokens(&hash, &[serde_test::Token::BorrowedBytes(expected)]);
+        let expected = "0002fda0";
+        serde_test::assert_tokens(&hash.readable(), &[serde_test::Token::Str(expected)]);
+        serde_test::assert_tokens(&hash.compact(), &tuple_tokens(&[0, 2, 0xfd, 0xa0]));
 
         let hash = Hash::<8>::new([0xff, 0xd9, 0xcc, 7, 0, 2, 0xfd, 0xa0]);
-        //let expected = "ffd9cc070002fda0";
-        //serde_test::assert_tokens(&hash, &[serde_test::Token::String(expected)]);
+        let expected = "ffd9cc070002fda0";
+        serde_test::assert_tokens(&hash.readable(), &[serde_test::Token::Str(expected)]);
         let expected = &[0xff, 0xd9, 0xcc, 7, 0, 2, 0xfd, 0xa0];
-        serde_test::assert_tokens(&hash, &[serde_test::Token::BorrowedBytes(expected)]);
+        serde_test::assert_tokens(&hash.compact(), &tuple_tokens(expected));
+
+        // both representations are accepted regardless of the format, as
+        // are byte strings
+        serde_test::assert_de_tokens(&hash.readable(), &[serde_test::Token::Bytes(expected)]);
+        serde_test::assert_de_tokens(&hash.compact(), &[serde_test::Token::Bytes(expected)]);
+        serde_test::assert_de_tokens(
+            &hash.compact(),
+            &[serde_test::Token::Str("ffd9cc070002fda0")],
+        );
+        serde_test::assert_de_tokens_error::<Readable<Hash<4>>>(
+            &[serde_test::Token::Str("ffd9cc070002fda0")],
+            "Deserialization error: input length was 16, expected 8",
+        );
+    }
+
+    #[test]
+    fn literals() {
+        const ADDRESS: Address = crate::address!("0x0123456789abcdeffedcba9876543210aabbccdd");
+        assert_eq!(
+            ADDRESS,
+            Address::try_from_str("0123456789abcdeffedcba9876543210aabbccdd").unwrap()
+        );
+        // mixed-case literals are checked against the checksum
+        assert_eq!(
+            crate::address!("0123456789AbcDeFFEdcBA9876543210AaBBccdD"),
+            ADDRESS
+        );
+        assert_eq!(
+            crate::address!("0123456789ABCDEFFEDCBA9876543210AABBCCDD"),
+            ADDRESS
+        );
+
+        let hash: H256 =
+            crate::hash!("00000000000000000000000000000000000000000000000000000000000000ff");
+        assert_eq!(hash[31], 0xff);
+        assert_eq!(crate::hash!("0xABcd"), Hash::new([0xab, 0xcd]));
+        assert_eq!(crate::hash!(""), Hash::<0>::zero());
+    }
+
+    #[test]
+    fn serde_formats() {
+        let hash = crate::address!("0123456789abcdeffedcba9876543210aabbccdd");
+
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!(json, "\"0123456789abcdeffedcba9876543210aabbccdd\"");
+        assert_eq!(serde_json::from_str::<Hash<20>>(&json).unwrap(), hash);
+        let json_array = serde_json::to_string(hash.as_bytes()).unwrap();
+        assert_eq!(serde_json::from_str::<Hash<20>>(&json_array).unwrap(), hash);
+        assert!(serde_json::from_str::<Hash<20>>("[1, 2, 3]").is_err());
+
+        // exactly the raw bytes without a length prefix
+        let bincode = bincode::serialize(&hash).unwrap();
+        assert_eq!(bincode, hash.as_bytes());
+        assert_eq!(bincode::deserialize::<Hash<20>>(&bincode).unwrap(), hash);
+        let postcard = postcard::to_allocvec(&hash).unwrap();
+        assert_eq!(postcard, hash.as_bytes());
+        assert_eq!(postcard::from_bytes::<Hash<20>>(&postcard).unwrap(), hash);
+
+        // 0x94 is the header of an array of 20 items
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&hash, &mut cbor).unwrap();
+        assert_eq!(cbor[0], 0x94);
+        assert_eq!(
+            ciborium::de::from_reader::<Hash<20>, _>(cbor.as_slice()).unwrap(),
+            hash
+        );
+        // and byte strings are accepted, 0x54 is the header of a 20 byte
+        // long byte string
+        let mut cbor = vec![0x54];
+        cbor.extend_from_slice(hash.as_bytes());
+        assert_eq!(
+            ciborium::de::from_reader::<Hash<20>, _>(cbor.as_slice()).unwrap(),
+            hash
+        );
+
+        assert!(bincode::deserialize::<Hash<32>>(&bincode).is_err());
     }
 
     #[test]
@@ -141,17 +353,94 @@ mod test {
         assert_eq!(address.to_string(), address_str);
     }
 
+    #[test]
+    fn formatting() {
+        let hash = Hash::<4>::new([0, 2, 0xfd, 0xa0]);
+        assert_eq!(format!("{}", hash), "0002fda0");
+        assert_eq!(format!("{:#}", hash), "0x0002fda0");
+        assert_eq!(format!("{:x}", hash), "0002fda0");
+        assert_eq!(format!("{:X}", hash), "0002FDA0");
+        assert_eq!(format!("{:#X}", hash), "0x0002FDA0");
+        assert_eq!(format!("{:?}", hash), "Hash<4>(0x0002fda0)");
+        assert_eq!(hash.to_base64(), "AAL9oA==");
+    }
+
+    #[test]
+    fn parsing() {
+        let hash = Hash::<4>::new([0, 2, 0xfd, 0xa0]);
+        assert_eq!("0x0002fda0".parse::<Hash<4>>(), Ok(hash));
+        assert_eq!("0002FDA0".parse::<Hash<4>>(), Ok(hash));
+        assert_eq!(format!("{:#}", hash).parse::<Hash<4>>(), Ok(hash));
+        assert_eq!(Hash::<4>::try_from_base64("AAL9oA=="), Ok(hash));
+        assert_eq!(
+            Hash::<4>::try_from_base64("AAL9"),
+            Err(Error::InvalidLength {
+                expected: 4,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        let bytes = [0, 2, 0xfd, 0xa0];
+        let hash = Hash::from(bytes);
+        assert_eq!(<[u8; 4]>::from(hash), bytes);
+        assert_eq!(hash.as_ref(), &bytes[..]);
+        assert_eq!(hash[2], 0xfd);
+        assert_eq!(&hash[1..3], &[2, 0xfd]);
+    }
+
+    #[test]
+    fn ordering_and_hashing() {
+        let small = Address::new([0; 20]);
+        let mut large = [0; 20];
+        large[0] = 1;
+        let large = Address::new(large);
+        assert!(small < large);
+
+        let set = [large, small, large]
+            .iter()
+            .copied()
+            .collect::<BTreeSet<_>>();
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![small, large]);
+
+        let mut balances = HashMap::new();
+        balances.insert(small, 10);
+        balances.insert(large, 20);
+        assert_eq!(balances[&small], 10);
+        assert_eq!(balances.get(&Address::zero()), Some(&10));
+    }
+
     #[test]
     fn from_invalid_string() {
         let invalid_len_string = "563fdea";
         let result = Hash::<4>::try_from_str(invalid_len_string);
-        assert_eq!(result, Err("input length was 7, expected 8".to_string()));
+        assert_eq!(
+            result,
+            Err(Error::InvalidLength {
+                expected: 8,
+                found: 7
+            })
+        );
 
-        let invalid_hex_string = "563fgdea";
+        let invalid_hex_string = "0x563fgdea";
         let result = Hash::<4>::try_from_str(invalid_hex_string);
         assert_eq!(
             result,
-            Err("cannot parse into hexadecimal: invalid digit found in string".to_string())
+            Err(Error::InvalidCharacter {
+                alphabet: Alphabet::Hex,
+                character: 'g',
+                position: 4
+            })
         );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid hex character 'g' at position 4"
+        );
+
+        // multi-byte characters don't break the parsing
+        assert!(Hash::<2>::try_from_str("é12").is_err());
+        assert!(Hash::<2>::try_from_str("+123").is_err());
     }
 }
@@ -0,0 +1,648 @@
+// 256-bit unsigned integer arithmetic.
+//
+// The number is stored as four 64-bit limbs in little endian order, i.e.
+// the first limb holds the least significant bits. Products and carries are
+// computed in 128-bit arithmetic. The operator traits panic on overflow and
+// division by zero like the primitive integers do in debug builds, the
+// checked, wrapping and saturating methods make the behavior explicit.
+
+use crate::error::{Alphabet, Error};
+use crate::H256;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Not, Rem, Shl, Shr, Sub};
+use std::str::FromStr;
+
+const LIMBS: usize = 4;
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, std::hash::Hash)]
+pub struct U256([u64; LIMBS]);
+
+impl U256 {
+    pub const ZERO: Self = Self([0; LIMBS]);
+    pub const ONE: Self = Self([1, 0, 0, 0]);
+    pub const MAX: Self = Self([u64::MAX; LIMBS]);
+    pub const BITS: u32 = 256;
+
+    /// Creates a number from its little endian 64-bit limbs.
+    pub const fn from_limbs(limbs: [u64; LIMBS]) -> Self {
+        Self(limbs)
+    }
+
+    pub const fn limbs(&self) -> [u64; LIMBS] {
+        self.0
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0_u64; LIMBS];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let start = 32 - 8 * (i + 1);
+            let mut limb_bytes = [0_u8; 8];
+            limb_bytes.copy_from_slice(&bytes[start..start + 8]);
+            *limb = u64::from_be_bytes(limb_bytes);
+        }
+        Self(limbs)
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0_u8; 32];
+        for (i, limb) in self.0.iter().enumerate() {
+            let start = 32 - 8 * (i + 1);
+            bytes[start..start + 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// The number of bits needed to represent the number.
+    pub fn bits(&self) -> u32 {
+        Self::BITS - self.leading_zeros()
+    }
+
+    pub fn leading_zeros(&self) -> u32 {
+        let mut zeros = 0;
+        for limb in self.0.iter().rev() {
+            zeros += limb.leading_zeros();
+            if *limb != 0 {
+                break;
+            }
+        }
+        zeros
+    }
+
+    /// Returns the least significant 64 bits.
+    pub fn low_u64(&self) -> u64 {
+        self.0[0]
+    }
+
+    /// Returns the number as `u64` if it fits.
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.0[1..].iter().all(|&limb| limb == 0) {
+            Some(self.0[0])
+        } else {
+            None
+        }
+    }
+
+    pub fn overflowing_add(self, other: Self) -> (Self, bool) {
+        let mut result = [0_u64; LIMBS];
+        let mut carry = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (sum, overflow_a) = self.0[i].overflowing_add(other.0[i]);
+            let (sum, overflow_b) = sum.overflowing_add(u64::from(carry));
+            *limb = sum;
+            carry = overflow_a || overflow_b;
+        }
+        (Self(result), carry)
+    }
+
+    pub fn overflowing_sub(self, other: Self) -> (Self, bool) {
+        let mut result = [0_u64; LIMBS];
+        let mut borrow = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (difference, overflow_a) = self.0[i].overflowing_sub(other.0[i]);
+            let (difference, overflow_b) = difference.overflowing_sub(u64::from(borrow));
+            *limb = difference;
+            borrow = overflow_a || overflow_b;
+        }
+        (Self(result), borrow)
+    }
+
+    pub fn overflowing_mul(self, other: Self) -> (Self, bool) {
+        // schoolbook multiplication, the limbs of the product beyond the
+        // fourth one only matter for detecting the overflow
+        let mut product = [0_u64; 2 * LIMBS];
+        for i in 0..LIMBS {
+            let mut carry = 0_u128;
+            for j in 0..LIMBS {
+                let current = u128::from(product[i + j])
+                    + u128::from(self.0[i]) * u128::from(other.0[j])
+                    + carry;
+                product[i + j] = current as u64;
+                carry = current >> 64;
+            }
+            product[i + LIMBS] = carry as u64;
+        }
+
+        let mut result = [0_u64; LIMBS];
+        result.copy_from_slice(&product[..LIMBS]);
+        let overflow = product[LIMBS..].iter().any(|&limb| limb != 0);
+        (Self(result), overflow)
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        match self.overflowing_add(other) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        match self.overflowing_sub(other) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        match self.overflowing_mul(other) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        self.checked_div_rem(other).map(|(quotient, _)| quotient)
+    }
+
+    pub fn checked_rem(self, other: Self) -> Option<Self> {
+        self.checked_div_rem(other).map(|(_, remainder)| remainder)
+    }
+
+    /// Returns the quotient and the remainder, or `None` if `other` is zero.
+    pub fn checked_div_rem(self, other: Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+
+        if let Some(divisor) = other.to_u64() {
+            let (quotient, remainder) = self.div_rem_u64(divisor);
+            return Some((quotient, Self::from(remainder)));
+        }
+
+        // binary long division, only the bits of the dividend below its
+        // highest set bit have to be visited
+        let mut quotient = Self::ZERO;
+        let mut remainder = Self::ZERO;
+        for bit in (0..self.bits()).rev() {
+            remainder = remainder << 1;
+            remainder.0[0] |= (self.0[bit as usize / 64] >> (bit % 64)) & 1;
+            if remainder >= other {
+                remainder = remainder - other;
+                quotient.0[bit as usize / 64] |= 1 << (bit % 64);
+            }
+        }
+        Some((quotient, remainder))
+    }
+
+    pub fn wrapping_add(self, other: Self) -> Self {
+        self.overflowing_add(other).0
+    }
+
+    pub fn wrapping_sub(self, other: Self) -> Self {
+        self.overflowing_sub(other).0
+    }
+
+    pub fn wrapping_mul(self, other: Self) -> Self {
+        self.overflowing_mul(other).0
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        self.checked_add(other).unwrap_or(Self::MAX)
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        self.checked_sub(other).unwrap_or(Self::ZERO)
+    }
+
+    pub fn saturating_mul(self, other: Self) -> Self {
+        self.checked_mul(other).unwrap_or(Self::MAX)
+    }
+
+    /// Parses a number in the given radix, which has to be 10 or 16. A `0x`
+    /// prefix is not allowed, see [`U256::from_str`] for that.
+    pub fn from_str_radix(string: &str, radix: u32) -> Result<Self, Error> {
+        let alphabet = match radix {
+            10 => Alphabet::Decimal,
+            16 => Alphabet::Hex,
+            _ => return Err(Error::UnsupportedRadix(radix)),
+        };
+
+        if string.is_empty() {
+            return Err(Error::Empty);
+        }
+
+        string
+            .chars()
+            .enumerate()
+            .try_fold(Self::ZERO, |number, (position, character)| {
+                let digit = character.to_digit(radix).ok_or(Error::InvalidCharacter {
+                    alphabet,
+                    character,
+                    position,
+                })?;
+
+                number
+                    .checked_mul(Self::from(u64::from(radix)))
+                    .and_then(|number| number.checked_add(Self::from(u64::from(digit))))
+                    .ok_or(Error::Overflow)
+            })
+    }
+
+    /// Divides by a single limb, which is a lot faster than the general
+    /// long division and good enough for radix conversions.
+    fn div_rem_u64(self, divisor: u64) -> (Self, u64) {
+        let mut quotient = [0_u64; LIMBS];
+        let mut remainder = 0_u128;
+        for i in (0..LIMBS).rev() {
+            let current = (remainder << 64) | u128::from(self.0[i]);
+            quotient[i] = (current / u128::from(divisor)) as u64;
+            remainder = current % u128::from(divisor);
+        }
+        (Self(quotient), remainder as u64)
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(value: u64) -> Self {
+        Self([value, 0, 0, 0])
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(value: u128) -> Self {
+        Self([value as u64, (value >> 64) as u64, 0, 0])
+    }
+}
+
+/// Interprets the hash as a big endian number.
+impl From<H256> for U256 {
+    fn from(hash: H256) -> Self {
+        Self::from_be_bytes(*hash.as_bytes())
+    }
+}
+
+impl From<U256> for H256 {
+    fn from(number: U256) -> Self {
+        H256::new(number.to_be_bytes())
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for U256 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.checked_add(other)
+            .expect("attempt to add with overflow")
+    }
+}
+
+impl Sub for U256 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.checked_sub(other)
+            .expect("attempt to subtract with overflow")
+    }
+}
+
+impl Mul for U256 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.checked_mul(other)
+            .expect("attempt to multiply with overflow")
+    }
+}
+
+impl Div for U256 {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        self.checked_div(other).expect("attempt to divide by zero")
+    }
+}
+
+impl Rem for U256 {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        self.checked_rem(other)
+            .expect("attempt to calculate the remainder with a divisor of zero")
+    }
+}
+
+/// Bits shifted beyond the 256th bit are discarded, shifting by 256 or more
+/// bits results in zero.
+impl Shl<u32> for U256 {
+    type Output = Self;
+
+    fn shl(self, shift: u32) -> Self {
+        let limb_shift = (shift / 64) as usize;
+        let bit_shift = shift % 64;
+        let mut result = [0_u64; LIMBS];
+        for (i, limb) in result.iter_mut().enumerate().skip(limb_shift) {
+            *limb = self.0[i - limb_shift] << bit_shift;
+            if bit_shift > 0 && i > limb_shift {
+                *limb |= self.0[i - limb_shift - 1] >> (64 - bit_shift);
+            }
+        }
+        Self(result)
+    }
+}
+
+impl Shr<u32> for U256 {
+    type Output = Self;
+
+    fn shr(self, shift: u32) -> Self {
+        let limb_shift = (shift / 64) as usize;
+        let bit_shift = shift % 64;
+        let mut result = [0_u64; LIMBS];
+        let kept = LIMBS.saturating_sub(limb_shift);
+        for (i, limb) in result.iter_mut().enumerate().take(kept) {
+            *limb = self.0[i + limb_shift] >> bit_shift;
+            if bit_shift > 0 && i + limb_shift + 1 < LIMBS {
+                *limb |= self.0[i + limb_shift + 1] << (64 - bit_shift);
+            }
+        }
+        Self(result)
+    }
+}
+
+impl Not for U256 {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(self.0.map(|limb| !limb))
+    }
+}
+
+impl BitAnd for U256 {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        let mut result = self.0;
+        result.iter_mut().zip(other.0).for_each(|(a, b)| *a &= b);
+        Self(result)
+    }
+}
+
+impl BitOr for U256 {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        let mut result = self.0;
+        result.iter_mut().zip(other.0).for_each(|(a, b)| *a |= b);
+        Self(result)
+    }
+}
+
+impl BitXor for U256 {
+    type Output = Self;
+
+    fn bitxor(self, other: Self) -> Self {
+        let mut result = self.0;
+        result.iter_mut().zip(other.0).for_each(|(a, b)| *a ^= b);
+        Self(result)
+    }
+}
+
+/// Parses a decimal number, or a hex number if prefixed with `0x`.
+impl FromStr for U256 {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.strip_prefix("0x") {
+            Some(hex) => Self::from_str_radix(hex, 16),
+            None => Self::from_str_radix(string, 10),
+        }
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return f.pad_integral(true, "", "0");
+        }
+
+        // 10^19 is the largest power of 10 that fits into a limb, so the
+        // number is converted 19 decimal digits at a time
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        let mut chunks = Vec::new();
+        let mut number = *self;
+        while !number.is_zero() {
+            let (quotient, remainder) = number.div_rem_u64(CHUNK);
+            chunks.push(remainder);
+            number = quotient;
+        }
+
+        let mut digits = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:019}", chunk));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::LowerHex for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut digits = format!("{:x}", H256::from(*self));
+        let zeros = digits.len() - digits.trim_start_matches('0').len();
+        digits.drain(..zeros.min(digits.len() - 1));
+        f.pad_integral(true, "0x", &digits)
+    }
+}
+
+impl fmt::UpperHex for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut digits = format!("{:X}", H256::from(*self));
+        let zeros = digits.len() - digits.trim_start_matches('0').len();
+        digits.drain(..zeros.min(digits.len() - 1));
+        f.pad_integral(true, "0x", &digits)
+    }
+}
+
+/// Human-readable formats get the number as a `0x` prefixed hex string,
+/// binary formats get the 32 big endian bytes like an [`H256`].
+impl Serialize for U256 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format!("{:#x}", self))
+        } else {
+            H256::from(*self).serialize(serializer)
+        }
+    }
+}
+
+/// Human-readable formats accept decimal strings as well.
+impl<'de> Deserialize<'de> for U256 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let string = String::deserialize(deserializer)?;
+            string
+                .parse()
+                .map_err(|e| serde::de::Error::custom(format!("Deserialization error: {}", e)))
+        } else {
+            H256::deserialize(deserializer).map(Self::from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn u(string: &str) -> U256 {
+        string.parse().unwrap()
+    }
+
+    #[test]
+    fn arithmetic() {
+        let max_u128 = U256::from(u128::MAX);
+        assert_eq!(max_u128 + U256::ONE, U256::from_limbs([0, 0, 1, 0]));
+        assert_eq!(
+            max_u128 * max_u128,
+            u("115792089237316195423570985008687907852589419931798687112530834793049593217025")
+        );
+        assert_eq!(U256::from_limbs([0, 0, 1, 0]) - U256::ONE, max_u128);
+        assert_eq!(
+            U256::MAX / U256::from(u128::MAX),
+            u("0x100000000000000000000000000000001")
+        );
+        assert_eq!(U256::MAX % max_u128, U256::ZERO);
+        assert_eq!(u("1000000000000000000000") % u("7"), U256::from(6_u64));
+        assert_eq!(
+            u("0x1234567890abcdef1234567890abcdef1234567890abcdef") / u("0x1234567890abcdef12345"),
+            u("0x1000000000000000000005aff727")
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(U256::MAX.checked_add(U256::ONE), None);
+        assert_eq!(U256::ZERO.checked_sub(U256::ONE), None);
+        assert_eq!(U256::MAX.checked_mul(U256::from(2_u64)), None);
+        assert_eq!(U256::ONE.checked_div(U256::ZERO), None);
+
+        assert_eq!(U256::MAX.wrapping_add(U256::from(2_u64)), U256::ONE);
+        assert_eq!(U256::ZERO.wrapping_sub(U256::ONE), U256::MAX);
+        assert_eq!(U256::MAX.wrapping_mul(U256::MAX), U256::ONE);
+
+        assert_eq!(U256::MAX.saturating_add(U256::ONE), U256::MAX);
+        assert_eq!(U256::ONE.saturating_sub(U256::MAX), U256::ZERO);
+        assert_eq!(U256::MAX.saturating_mul(U256::MAX), U256::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to add with overflow")]
+    fn add_overflow_panics() {
+        let _ = U256::MAX + U256::ONE;
+    }
+
+    #[test]
+    fn shifts_and_bits() {
+        assert_eq!(
+            U256::ONE << 255,
+            u("0x8000000000000000000000000000000000000000000000000000000000000000")
+        );
+        assert_eq!((U256::ONE << 255) >> 255, U256::ONE);
+        assert_eq!(U256::ONE << 256, U256::ZERO);
+        assert_eq!(U256::MAX >> 300, U256::ZERO);
+        assert_eq!(u("0xabcdef") << 68, u("0xabcdef00000000000000000"));
+        assert_eq!(u("0xabcdef00000000000000000") >> 68, u("0xabcdef"));
+
+        assert_eq!(U256::ZERO.bits(), 0);
+        assert_eq!(U256::ONE.bits(), 1);
+        assert_eq!((U256::ONE << 100).bits(), 101);
+        assert_eq!(U256::MAX.leading_zeros(), 0);
+        assert_eq!(!U256::ZERO, U256::MAX);
+        assert_eq!(u("0xff00") & u("0x0ff0"), u("0x0f00"));
+        assert_eq!(u("0xff00") | u("0x0ff0"), u("0xfff0"));
+        assert_eq!(u("0xff00") ^ u("0x0ff0"), u("0xf0f0"));
+    }
+
+    #[test]
+    fn comparison() {
+        assert!(
+            U256::from_limbs([0, 0, 0, 1]) > U256::from_limbs([u64::MAX, u64::MAX, u64::MAX, 0])
+        );
+        assert!(U256::from(5_u64) < U256::from(6_u64));
+        assert_eq!(U256::MAX.max(U256::ZERO), U256::MAX);
+    }
+
+    #[test]
+    fn parsing_and_formatting() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(u(max), U256::MAX);
+        assert_eq!(U256::MAX.to_string(), max);
+        assert_eq!(U256::ZERO.to_string(), "0");
+        assert_eq!(
+            U256::from(10_000_000_000_000_000_000_u64).to_string(),
+            "10000000000000000000"
+        );
+        assert_eq!(format!("{:>5}", U256::from(42_u64)), "   42");
+
+        assert_eq!(format!("{:x}", U256::ZERO), "0");
+        assert_eq!(format!("{:#x}", U256::from(0xabc_u64)), "0xabc");
+        assert_eq!(format!("{:X}", U256::from(0xabc_u64)), "ABC");
+        assert_eq!(U256::from_str_radix("ABC", 16), Ok(U256::from(0xabc_u64)));
+
+        assert_eq!(format!("{}0", max).parse::<U256>(), Err(Error::Overflow));
+        assert_eq!(
+            "12a".parse::<U256>(),
+            Err(Error::InvalidCharacter {
+                alphabet: Alphabet::Decimal,
+                character: 'a',
+                position: 2
+            })
+        );
+        assert_eq!("0x".parse::<U256>(), Err(Error::Empty));
+        assert_eq!(
+            U256::from_str_radix("1", 8),
+            Err(Error::UnsupportedRadix(8))
+        );
+    }
+
+    #[test]
+    fn hash_conversion() {
+        let mut bytes = [0_u8; 32];
+        bytes[0] = 0x01;
+        bytes[31] = 0xff;
+        let hash = H256::new(bytes);
+        let number = U256::from(hash);
+        assert_eq!(number, (U256::ONE << 248) + U256::from(0xff_u64));
+        assert_eq!(H256::from(number), hash);
+
+        // a hash below the target
+        let target = U256::ONE << 240;
+        let mut bytes = [0_u8; 32];
+        bytes[2] = 0xff;
+        assert!(U256::from(H256::new(bytes)) < target);
+        bytes[1] = 0x01;
+        assert!(U256::from(H256::new(bytes)) > target);
+    }
+
+    #[test]
+    fn serde() {
+        let number = U256::from(0xabc_u64);
+        assert_eq!(serde_json::to_string(&number).unwrap(), "\"0xabc\"");
+        assert_eq!(serde_json::from_str::<U256>("\"0xabc\"").unwrap(), number);
+        assert_eq!(serde_json::from_str::<U256>("\"2748\"").unwrap(), number);
+
+        let bytes = bincode::serialize(&number).unwrap();
+        assert_eq!(bytes, H256::from(number).as_bytes());
+        assert_eq!(bincode::deserialize::<U256>(&bytes).unwrap(), number);
+    }
+}
@@ -0,0 +1,552 @@
+// Checksummed textual encodings of addresses.
+//
+// The plain hex form of an address carries no redundancy, so a single typo
+// yields a different, perfectly valid address. The Base58Check and
+// Bech32(m) encodings add a checksum and a network marker, therefore typos
+// and addresses of the wrong network are rejected when decoding.
+//
+// An [`EncodedAddress`] carries the encoding it was parsed from or created
+// with, and displays and serializes itself in that encoding, so a testnet
+// address stays a testnet address when it is passed around.
+
+use crate::{base58check, Error, Hasher, Sha256};
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zgc_common::bech32::{self, Variant};
+use zgc_common::encoding::{Decode, Encode};
+use zgc_common::{Address, Network};
+
+const MAINNET_VERSION: u8 = 0x50;
+const TESTNET_VERSION: u8 = 0x6f;
+const MAINNET_HRP: &str = "zgc";
+const TESTNET_HRP: &str = "tzgc";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AddressFormat {
+    /// Plain lowercase hex without a checksum.
+    Hex,
+    /// Hex with the checksum encoded in the letter case, see
+    /// [`ChecksumAddress`].
+    ChecksumHex,
+    Base58Check,
+    Bech32,
+    Bech32m,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AddressEncoding {
+    pub format: AddressFormat,
+    pub network: Network,
+}
+
+impl AddressEncoding {
+    pub const fn new(format: AddressFormat, network: Network) -> Self {
+        Self { format, network }
+    }
+
+    pub fn encode(&self, address: &Address) -> String {
+        match self.format {
+            AddressFormat::Hex => address.to_string(),
+            AddressFormat::ChecksumHex => ChecksumAddress(*address).to_string(),
+            AddressFormat::Base58Check => {
+                base58check::encode(version(self.network), address.as_bytes())
+            }
+            AddressFormat::Bech32 | AddressFormat::Bech32m => {
+                // unwrap is fine because the hrp is valid and 20 bytes always
+                // fit into a bech32 string
+                bech32::encode(hrp(self.network), address.as_bytes(), self.variant()).unwrap()
+            }
+        }
+    }
+
+    pub fn decode(&self, string: &str) -> Result<Address, Error> {
+        match self.format {
+            AddressFormat::Hex => Ok(Address::try_from_str(string)?),
+            AddressFormat::ChecksumHex => {
+                Ok(ChecksumAddress::try_from_str(string, ChecksumMode::Strict)?.0)
+            }
+            AddressFormat::Base58Check => {
+                let (version, payload) = base58check::decode(string)?;
+                let network = match version {
+                    MAINNET_VERSION => Network::Mainnet,
+                    TESTNET_VERSION => Network::Testnet,
+                    _ => return Err(Error::UnknownAddressVersion(version)),
+                };
+                self.check_network(network)?;
+
+                Ok(Address::try_from(payload.as_slice())?)
+            }
+            AddressFormat::Bech32 | AddressFormat::Bech32m => {
+                let (hrp, data, variant) = bech32::decode(string)?;
+                let network = match hrp.as_str() {
+                    MAINNET_HRP => Network::Mainnet,
+                    TESTNET_HRP => Network::Testnet,
+                    _ => return Err(Error::UnknownAddressPrefix(hrp)),
+                };
+                self.check_network(network)?;
+
+                if variant != self.variant() {
+                    return Err(Error::WrongChecksumVariant {
+                        expected: self.variant(),
+                        found: variant,
+                    });
+                }
+
+                Ok(Address::try_from(data.as_slice())?)
+            }
+        }
+    }
+
+    fn variant(&self) -> Variant {
+        match self.format {
+            AddressFormat::Bech32 => Variant::Bech32,
+            _ => Variant::Bech32m,
+        }
+    }
+
+    fn check_network(&self, network: Network) -> Result<(), Error> {
+        if network != self.network {
+            return Err(zgc_common::Error::WrongNetwork {
+                expected: self.network,
+                found: network,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Bech32m on mainnet.
+impl Default for AddressEncoding {
+    fn default() -> Self {
+        Self::new(AddressFormat::Bech32m, Network::Mainnet)
+    }
+}
+
+/// An address together with the encoding it is displayed and serialized in.
+///
+/// Parsing accepts every [`AddressFormat`] and keeps the encoding of the
+/// input. The plain and checksummed hex forms carry no network marker, they
+/// are parsed as mainnet.
+///
+/// The encoding is presentation only: two encoded addresses are equal if
+/// their addresses are, and binary formats contain only the address.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EncodedAddress {
+    pub address: Address,
+    pub encoding: AddressEncoding,
+}
+
+impl EncodedAddress {
+    pub const fn new(address: Address, encoding: AddressEncoding) -> Self {
+        Self { address, encoding }
+    }
+}
+
+impl From<Address> for EncodedAddress {
+    fn from(address: Address) -> Self {
+        Self::new(address, AddressEncoding::default())
+    }
+}
+
+impl PartialEq for EncodedAddress {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address
+    }
+}
+
+impl Eq for EncodedAddress {}
+
+impl fmt::Display for EncodedAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.encoding.encode(&self.address))
+    }
+}
+
+impl FromStr for EncodedAddress {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self, Error> {
+        let digits = string.trim_start_matches("0x");
+        let lowercase = string.to_ascii_lowercase();
+
+        let encoding = if digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            let mixed_case = digits.chars().any(|c| c.is_ascii_lowercase())
+                && digits.chars().any(|c| c.is_ascii_uppercase());
+            let format = if mixed_case {
+                AddressFormat::ChecksumHex
+            } else {
+                AddressFormat::Hex
+            };
+            AddressEncoding::new(format, Network::Mainnet)
+        } else if lowercase.starts_with(MAINNET_HRP) || lowercase.starts_with(TESTNET_HRP) {
+            let (hrp, _, variant) = bech32::decode(string)?;
+            let format = match variant {
+                Variant::Bech32 => AddressFormat::Bech32,
+                Variant::Bech32m => AddressFormat::Bech32m,
+            };
+            let network = match hrp.as_str() {
+                MAINNET_HRP => Network::Mainnet,
+                TESTNET_HRP => Network::Testnet,
+                _ => return Err(Error::UnknownAddressPrefix(hrp)),
+            };
+            AddressEncoding::new(format, network)
+        } else {
+            let (version, _) = base58check::decode(string)?;
+            let network = match version {
+                MAINNET_VERSION => Network::Mainnet,
+                TESTNET_VERSION => Network::Testnet,
+                _ => return Err(Error::UnknownAddressVersion(version)),
+            };
+            AddressEncoding::new(AddressFormat::Base58Check, network)
+        };
+
+        Ok(Self::new(encoding.decode(string)?, encoding))
+    }
+}
+
+/// Human-readable formats (e.g. JSON) get the address in its encoding,
+/// other formats get the address as is.
+impl Serialize for EncodedAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            self.address.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EncodedAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let string = String::deserialize(deserializer)?;
+            string
+                .parse()
+                .map_err(|e| D::Error::custom(format!("Deserialization error: {}", e)))
+        } else {
+            Ok(Address::deserialize(deserializer)?.into())
+        }
+    }
+}
+
+impl Encode for EncodedAddress {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.address.encode(writer)
+    }
+}
+
+impl Decode for EncodedAddress {
+    fn decode<R: Read>(reader: &mut R) -> Result<Self, zgc_common::Error> {
+        Ok(Address::decode(reader)?.into())
+    }
+}
+
+/// Determines how the letter case of a checksummed hex address is checked.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ChecksumMode {
+    /// The input has to be in checksummed mixed-case form.
+    Strict,
+    /// All-lowercase and all-uppercase inputs are accepted without a
+    /// checksum, mixed-case inputs have to be checksummed.
+    Lenient,
+}
+
+/// An address in mixed-case checksummed hex form.
+///
+/// Every letter of the lowercase hex form is capitalized if the
+/// corresponding nibble in the SHA-256 digest of the lowercase form is at
+/// least 8 (as in EIP-55), so a typo changes the expected letter case with
+/// high probability.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ChecksumAddress(pub Address);
+
+impl ChecksumAddress {
+    /// Parses a hex address with an optional `0x` prefix and checks its
+    /// letter case as required by `mode`.
+    pub fn try_from_str(string: &str, mode: ChecksumMode) -> Result<Self, Error> {
+        let address = Address::try_from_str(string)?;
+        let digits = string.trim_start_matches("0x");
+
+        let has_lowercase = digits.chars().any(|c| c.is_ascii_lowercase());
+        let has_uppercase = digits.chars().any(|c| c.is_ascii_uppercase());
+        if mode == ChecksumMode::Lenient && !(has_lowercase && has_uppercase) {
+            return Ok(Self(address));
+        }
+
+        let checksummed = Self(address).to_string();
+        if let Some((position, (found, expected))) = digits
+            .chars()
+            .zip(checksummed.chars())
+            .enumerate()
+            .find(|(_, (found, expected))| found != expected)
+        {
+            return Err(zgc_common::Error::InvalidChecksumCase {
+                position,
+                expected,
+                found,
+            }
+            .into());
+        }
+
+        Ok(Self(address))
+    }
+}
+
+impl fmt::Display for ChecksumAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lowercase = self.0.to_string();
+        let hash = Sha256::new().digest(lowercase.clone());
+
+        let checksummed: String = lowercase
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let byte = hash[i / 2];
+                let nibble = if i.is_multiple_of(2) {
+                    byte >> 4
+                } else {
+                    byte & 0x0f
+                };
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+        f.write_str(&checksummed)
+    }
+}
+
+fn version(network: Network) -> u8 {
+    match network {
+        Network::Mainnet => MAINNET_VERSION,
+        Network::Testnet => TESTNET_VERSION,
+    }
+}
+
+fn hrp(network: Network) -> &'static str {
+    match network {
+        Network::Mainnet => MAINNET_HRP,
+        Network::Testnet => TESTNET_HRP,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn address() -> Address {
+        zgc_common::address!("0123456789abcdeffedcba9876543210aabbccdd")
+    }
+
+    #[test]
+    fn round_trip() {
+        let formats = [
+            AddressFormat::Hex,
+            AddressFormat::ChecksumHex,
+            AddressFormat::Base58Check,
+            AddressFormat::Bech32,
+            AddressFormat::Bech32m,
+        ];
+
+        for &format in &formats {
+            for &network in &[Network::Mainnet, Network::Testnet] {
+                let encoding = AddressEncoding::new(format, network);
+                let encoded = encoding.encode(&address());
+                assert_eq!(encoding.decode(&encoded), Ok(address()));
+            }
+        }
+    }
+
+    #[test]
+    fn encodings() {
+        let encoding = AddressEncoding::new(AddressFormat::Bech32m, Network::Mainnet);
+        assert_eq!(
+            encoding.encode(&address()),
+            "zgc1qy352euf40x7llkuh2v8v4pjzz4thnxa27vgup"
+        );
+        let encoding = AddressEncoding::new(AddressFormat::Bech32, Network::Testnet);
+        assert_eq!(
+            encoding.encode(&address()),
+            "tzgc1qy352euf40x7llkuh2v8v4pjzz4thnxa3h4qej"
+        );
+        let encoding = AddressEncoding::new(AddressFormat::Base58Check, Network::Mainnet);
+        assert_eq!(
+            encoding.encode(&address()),
+            "ZC9GhZQ6CJPFtdKv6jN7CDMiqANadrDdz9"
+        );
+    }
+
+    #[test]
+    fn checksum_hex() {
+        let encoding = AddressEncoding::new(AddressFormat::ChecksumHex, Network::Mainnet);
+        assert_eq!(
+            encoding.encode(&address()),
+            "0123456789AbcDeFFEdcBA9876543210AaBBccdD"
+        );
+        assert_eq!(
+            encoding.decode("0x0123456789AbcDeFFEdcBA9876543210AaBBccdD"),
+            Ok(address())
+        );
+        assert_eq!(
+            encoding.decode("0123456789AbcDeFFEdcBA9876543210AaBBccdd"),
+            Err(Error::Common(zgc_common::Error::InvalidChecksumCase {
+                position: 39,
+                expected: 'D',
+                found: 'd'
+            }))
+        );
+        // a typo in a digit changes the expected letter case
+        assert!(encoding
+            .decode("0123456789AbcDeFFEdcBA9876543211AaBBccdD")
+            .is_err());
+    }
+
+    #[test]
+    fn checksum_modes() {
+        let checksummed = "0123456789AbcDeFFEdcBA9876543210AaBBccdD";
+        assert_eq!(ChecksumAddress(address()).to_string(), checksummed);
+
+        for &mode in &[ChecksumMode::Strict, ChecksumMode::Lenient] {
+            assert_eq!(
+                ChecksumAddress::try_from_str(checksummed, mode),
+                Ok(ChecksumAddress(address()))
+            );
+            assert_eq!(
+                ChecksumAddress::try_from_str("0x0123456789aBcDeFFEdcBA9876543210AaBBccdD", mode),
+                Err(Error::Common(zgc_common::Error::InvalidChecksumCase {
+                    position: 10,
+                    expected: 'A',
+                    found: 'a'
+                }))
+            );
+        }
+
+        // only mixed-case input carries a checksum
+        for &string in &[
+            "0123456789abcdeffedcba9876543210aabbccdd",
+            "0123456789ABCDEFFEDCBA9876543210AABBCCDD",
+        ] {
+            assert_eq!(
+                ChecksumAddress::try_from_str(string, ChecksumMode::Lenient),
+                Ok(ChecksumAddress(address()))
+            );
+            assert!(ChecksumAddress::try_from_str(string, ChecksumMode::Strict).is_err());
+        }
+
+        assert_eq!(
+            ChecksumAddress::try_from_str("aA00", ChecksumMode::Strict),
+            Err(Error::Common(zgc_common::Error::InvalidLength {
+                expected: 40,
+                found: 4
+            }))
+        );
+    }
+
+    #[test]
+    fn typos_are_detected() {
+        let encoding = AddressEncoding::new(AddressFormat::Bech32m, Network::Mainnet);
+        assert_eq!(
+            encoding.decode("zgc1qy352euf40x7llkuh2v8v4pjzz4thnxa27vgur"),
+            Err(Error::Common(zgc_common::Error::InvalidBech32Checksum))
+        );
+        assert_eq!(
+            encoding.decode("tzgc1qy352euf40x7llkuh2v8v4pjzz4thnxa3h4qej"),
+            Err(Error::Common(zgc_common::Error::WrongNetwork {
+                expected: Network::Mainnet,
+                found: Network::Testnet
+            }))
+        );
+        assert_eq!(
+            encoding.decode("abc1qy352euf40x7llkuh2v8v4pjzz4thnxay8tqsn"),
+            Err(Error::UnknownAddressPrefix("abc".to_string()))
+        );
+
+        let encoding = AddressEncoding::new(AddressFormat::Bech32, Network::Testnet);
+        assert!(encoding
+            .decode("tzgc1qy352euf40x7llkuh2v8v4pjzz4thnxa3h4qej")
+            .is_ok());
+        let encoding = AddressEncoding::new(AddressFormat::Bech32m, Network::Testnet);
+        assert_eq!(
+            encoding.decode("tzgc1qy352euf40x7llkuh2v8v4pjzz4thnxa3h4qej"),
+            Err(Error::WrongChecksumVariant {
+                expected: Variant::Bech32m,
+                found: Variant::Bech32
+            })
+        );
+
+        let encoding = AddressEncoding::new(AddressFormat::Base58Check, Network::Mainnet);
+        assert!(encoding
+            .decode("ZC9GhZQ6CJPFtdKv6jN7CDMiqANadrDdz8")
+            .is_err());
+    }
+
+    #[test]
+    fn encoded_address() {
+        let strings = [
+            "0123456789abcdeffedcba9876543210aabbccdd",
+            "0123456789AbcDeFFEdcBA9876543210AaBBccdD",
+            "ZC9GhZQ6CJPFtdKv6jN7CDMiqANadrDdz9",
+            "tzgc1qy352euf40x7llkuh2v8v4pjzz4thnxa3h4qej",
+            "zgc1qy352euf40x7llkuh2v8v4pjzz4thnxa27vgup",
+            "tzgc1qy352euf40x7llkuh2v8v4pjzz4thnxayt9vus",
+        ];
+
+        for &string in &strings {
+            let encoded: EncodedAddress = string.parse().unwrap();
+            assert_eq!(encoded.address, address());
+            // the encoding of the input is kept
+            assert_eq!(encoded.to_string(), string);
+        }
+
+        let encoded: EncodedAddress = "tzgc1qy352euf40x7llkuh2v8v4pjzz4thnxayt9vus"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            encoded.encoding,
+            AddressEncoding::new(AddressFormat::Bech32m, Network::Testnet)
+        );
+        assert_eq!(
+            "zgc1qy352euf40x7llkuh2v8v4pjzz4thnxa27vgur".parse::<EncodedAddress>(),
+            Err(Error::Common(zgc_common::Error::InvalidBech32Checksum))
+        );
+        assert_eq!(
+            EncodedAddress::from(address()).to_string(),
+            "zgc1qy352euf40x7llkuh2v8v4pjzz4thnxa27vgup"
+        );
+    }
+
+    #[test]
+    fn serde_encodings() {
+        #[derive(::serde::Serialize, ::serde::Deserialize, Debug, PartialEq)]
+        struct Payment {
+            mainnet: EncodedAddress,
+            testnet: EncodedAddress,
+        }
+
+        let testnet = AddressEncoding::new(AddressFormat::Bech32m, Network::Testnet);
+        let payment = Payment {
+            mainnet: address().into(),
+            testnet: EncodedAddress::new(address(), testnet),
+        };
+        let json = serde_json::to_string(&payment).unwrap();
+        assert_eq!(
+            json,
+            r#"{"mainnet":"zgc1qy352euf40x7llkuh2v8v4pjzz4thnxa27vgup","testnet":"tzgc1qy352euf40x7llkuh2v8v4pjzz4thnxayt9vus"}"#
+        );
+        let decoded: Payment = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, payment);
+        assert_eq!(decoded.testnet.encoding, testnet);
+        // a typo is rejected
+        let typo = json.replacen("27vgup", "27vgur", 1);
+        assert!(serde_json::from_str::<Payment>(&typo).is_err());
+    }
+}
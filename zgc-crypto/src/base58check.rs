@@ -0,0 +1,105 @@
+// Base58Check encoding.
+//
+// A version byte is prepended to the payload and the first 4 bytes of the
+// double SHA-256 digest of the versioned payload are appended as a checksum
+// before the whole byte stream is base58 encoded. A mistyped character
+// changes the checksum with overwhelming probability, so typos are detected
+// instead of silently decoding into different data.
+
+use crate::{Error, Hasher, Sha256};
+
+use zgc_common::base58;
+
+const CHECKSUM_LEN: usize = 4;
+
+/// Encodes the versioned payload into a Base58Check `String`.
+pub fn encode(version: u8, payload: &[u8]) -> String {
+    let mut bytes = Vec::with_capacity(1 + payload.len() + CHECKSUM_LEN);
+    bytes.push(version);
+    bytes.extend_from_slice(payload);
+
+    let checksum = checksum(&bytes);
+    bytes.extend_from_slice(&checksum);
+
+    base58::encode(&bytes)
+}
+
+/// Decodes a Base58Check string slice into its version byte and payload.
+pub fn decode(string: &str) -> Result<(u8, Vec<u8>), Error> {
+    let mut bytes = base58::decode(string)?;
+
+    if bytes.len() < 1 + CHECKSUM_LEN {
+        return Err(zgc_common::Error::TooShort {
+            minimum: 1 + CHECKSUM_LEN,
+            found: bytes.len(),
+        }
+        .into());
+    }
+
+    let mut found = [0_u8; CHECKSUM_LEN];
+    found.copy_from_slice(&bytes.split_off(bytes.len() - CHECKSUM_LEN));
+    let expected = checksum(&bytes);
+    if found != expected {
+        return Err(Error::ChecksumMismatch { expected, found });
+    }
+
+    let payload = bytes.split_off(1);
+    Ok((bytes[0], payload))
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hasher = Sha256::new();
+    let digest = hasher.digest_bytes(hasher.digest_bytes(bytes).as_bytes());
+
+    let mut checksum = [0_u8; CHECKSUM_LEN];
+    checksum.copy_from_slice(&digest.as_bytes()[..CHECKSUM_LEN]);
+    checksum
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let payload = [0xab; 20];
+        let encoded = encode(0x35, &payload);
+        assert_eq!(decode(&encoded), Ok((0x35, payload.to_vec())));
+
+        let encoded = encode(0, &[]);
+        assert_eq!(decode(&encoded), Ok((0, vec![])));
+    }
+
+    #[test]
+    fn bitcoin_address() {
+        // version 0 address of the hash160 of the generator point's public key
+        let payload = [
+            0x75, 0x1e, 0x76, 0xe8, 0x19, 0x91, 0x96, 0xd4, 0x54, 0x94, 0x1c, 0x45, 0xd1, 0xb3,
+            0xa3, 0x23, 0xf1, 0x43, 0x3b, 0xd6,
+        ];
+        assert_eq!(encode(0, &payload), "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH");
+    }
+
+    #[test]
+    fn invalid_checksum() {
+        let result = decode("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMJ");
+        assert_eq!(
+            result,
+            Err(Error::ChecksumMismatch {
+                expected: [0x51, 0x0d, 0x16, 0x34],
+                found: [0x51, 0x0d, 0x16, 0x35]
+            })
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "checksum mismatch: expected 510d1634, found 510d1635"
+        );
+        assert_eq!(
+            decode("1111"),
+            Err(Error::Common(zgc_common::Error::TooShort {
+                minimum: 5,
+                found: 4
+            }))
+        );
+    }
+}
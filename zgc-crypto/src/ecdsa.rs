@@ -0,0 +1,120 @@
+// ECDSA over the secp256k1 curve.
+//
+// Private keys are 32 byte scalars (H256), public keys are stored in their
+// compressed SEC1 form (33 bytes) and signatures are the 64 byte
+// concatenation of the `r` and `s` values. Messages are always digested
+// before signing, therefore every function here works with an already
+// hashed (H256) message.
+
+use crate::Error;
+
+use std::convert::TryFrom;
+
+use k256::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+use k256::ecdsa::{Signature as EcdsaSignature, SigningKey, VerifyingKey};
+
+use zgc_common::{PublicKey, Signature, H256};
+
+/// Derives the compressed public key belonging to a private key.
+///
+/// Fails if the private key is not a valid secp256k1 scalar, i.e. it is
+/// zero or not less than the curve order.
+pub fn derive_public_key(private_key: &H256) -> Result<PublicKey, Error> {
+    let signing_key = signing_key(private_key)?;
+    let encoded = signing_key.verifying_key().to_encoded_point(true);
+
+    Ok(PublicKey::try_from(encoded.as_bytes())?)
+}
+
+/// Checks whether the private key is a valid secp256k1 scalar.
+///
+/// It is a lot cheaper than [`derive_public_key`] because no curve
+/// arithmetic is involved.
+pub fn is_valid_private_key(private_key: &H256) -> bool {
+    signing_key(private_key).is_ok()
+}
+
+/// Signs an already digested message with the provided private key.
+///
+/// Signatures are deterministic (RFC 6979), so signing the same message
+/// twice with the same key yields the same signature.
+pub fn sign(private_key: &H256, message: &H256) -> Result<Signature, Error> {
+    let signing_key = signing_key(private_key)?;
+    let signature: EcdsaSignature = signing_key
+        .sign_prehash(message.as_bytes())
+        .map_err(|_| Error::SigningFailed)?;
+
+    Ok(Signature::try_from(&signature.to_bytes()[..])?)
+}
+
+/// Checks whether `signature` was created by the owner of `public_key`
+/// over `message`.
+pub fn verify(public_key: &PublicKey, message: &H256, signature: &Signature) -> bool {
+    let verifying_key = match VerifyingKey::from_sec1_bytes(public_key.as_bytes()) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+    let signature = match EcdsaSignature::from_slice(signature.as_bytes()) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+
+    verifying_key
+        .verify_prehash(message.as_bytes(), &signature)
+        .is_ok()
+}
+
+fn signing_key(private_key: &H256) -> Result<SigningKey, Error> {
+    SigningKey::from_slice(private_key.as_bytes()).map_err(|_| Error::InvalidPrivateKey)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Hasher, Sha256};
+
+    #[test]
+    fn sign_and_verify() {
+        let hasher = Sha256::new();
+        let private_key = hasher.digest(String::from("alice"));
+        let public_key = derive_public_key(&private_key).expect("invalid private key");
+        let message = hasher.digest(String::from("hello world"));
+
+        let signature = sign(&private_key, &message).expect("failed to sign");
+        assert!(verify(&public_key, &message, &signature));
+        // deterministic signatures
+        assert_eq!(sign(&private_key, &message), Ok(signature));
+
+        let other_message = hasher.digest(String::from("hello world!"));
+        assert!(!verify(&public_key, &other_message, &signature));
+
+        let other_key = derive_public_key(&hasher.digest(String::from("bob"))).unwrap();
+        assert!(!verify(&other_key, &message, &signature));
+    }
+
+    #[test]
+    fn invalid_private_key() {
+        assert_eq!(
+            derive_public_key(&H256::zero()),
+            Err(Error::InvalidPrivateKey)
+        );
+        assert_eq!(
+            sign(&H256::new([0xff; 32]), &H256::zero()),
+            Err(Error::InvalidPrivateKey)
+        );
+        assert!(!is_valid_private_key(&H256::zero()));
+        assert!(!is_valid_private_key(&H256::new([0xff; 32])));
+        assert!(is_valid_private_key(&H256::new([0x11; 32])));
+    }
+
+    #[test]
+    fn compressed_public_key() {
+        let private_key =
+            zgc_common::hash!("0000000000000000000000000000000000000000000000000000000000000001");
+        // the generator point of secp256k1
+        assert_eq!(
+            derive_public_key(&private_key).unwrap().to_string(),
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt;
+use zgc_common::bech32::Variant;
+
+/// Errors of the cryptographic primitives and address encodings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Parsing or decoding a common type failed.
+    Common(zgc_common::Error),
+    /// The private key is zero or not less than the secp256k1 curve order.
+    InvalidPrivateKey,
+    /// The ECDSA signing algorithm failed.
+    SigningFailed,
+    /// The Base58Check checksum doesn't match the payload.
+    ChecksumMismatch { expected: [u8; 4], found: [u8; 4] },
+    /// The version byte of a Base58Check address belongs to no network.
+    UnknownAddressVersion(u8),
+    /// The prefix of a bech32 address belongs to no network.
+    UnknownAddressPrefix(String),
+    /// A bech32 address has the checksum of the other bech32 variant.
+    WrongChecksumVariant { expected: Variant, found: Variant },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Common(e) => write!(f, "invalid input: {}", e),
+            Self::InvalidPrivateKey => f.write_str("private key is not a valid secp256k1 scalar"),
+            Self::SigningFailed => f.write_str("cannot sign message"),
+            Self::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch: expected {}, found {}",
+                to_hex(expected),
+                to_hex(found)
+            ),
+            Self::UnknownAddressVersion(version) => {
+                write!(f, "unknown address version 0x{:02x}", version)
+            }
+            Self::UnknownAddressPrefix(prefix) => write!(f, "unknown address prefix {:?}", prefix),
+            Self::WrongChecksumVariant { expected, found } => write!(
+                f,
+                "address has a {:?} checksum, expected {:?}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Common(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<zgc_common::Error> for Error {
+    fn from(e: zgc_common::Error) -> Self {
+        Self::Common(e)
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
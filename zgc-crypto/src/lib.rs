@@ -1,11 +1,18 @@
 // This flag enables the use of array_chunks in nightly mode
 #![feature(array_chunks)]
 
+pub mod address;
+pub mod base58check;
 mod consts;
+mod ecdsa;
+mod error;
 mod sha256;
 
+pub use ecdsa::{derive_public_key, is_valid_private_key, sign, verify};
+pub use error::Error;
 pub use sha256::Sha256;
 
 pub trait Hasher {
     fn digest(&self, input: String) -> zgc_common::H256;
+    fn digest_bytes(&self, input: &[u8]) -> zgc_common::H256;
 }
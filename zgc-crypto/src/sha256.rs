@@ -29,34 +29,44 @@ impl Sha256 {
 
 impl Hasher for Sha256 {
     fn digest(&self, input: String) -> H256 {
-        let processed = preprocess(input);
-        // since HASHES [u8; 8] is a Copy type (because it's not expensive to copy
-        // 8 u32 numbers) it doesn't get moved out of scope, it is simply copied
-        // into `hashes`
-        let mut hashes = HASHES;
-        // for each chunk, schedule them and compress them
-        processed.chunks(64).for_each(|chunk| {
-            let scheduled = schedule(chunk);
-            compress(&mut hashes, &scheduled);
-        });
-
-        // digest the 8 u32 hash values that were
-        // successively modified in the chunk loop
-        //
-        // array_chunks_mut gives you a mutable reference to
-        // a [u8; 4] fixed array in which we can copy
-        // the i^th u32 of `hashes` converted into big
-        // endian bytes.
-        let mut digest = [0_u8; 32];
-        digest
-            .array_chunks_mut::<4>()
-            .enumerate()
-            .for_each(|(i, chunk)| chunk.copy_from_slice(&hashes[i].to_be_bytes()));
-
-        H256::new(digest)
+        hash(&preprocess(input))
+    }
+
+    fn digest_bytes(&self, input: &[u8]) -> H256 {
+        // the input is only borrowed here, so it has to be copied into a new
+        // Vec<u8> that can be padded
+        hash(&pad(input.to_vec()))
     }
 }
 
+/// Computes the hash of already preprocessed (padded) data.
+fn hash(processed: &[u8]) -> H256 {
+    // since HASHES [u8; 8] is a Copy type (because it's not expensive to copy
+    // 8 u32 numbers) it doesn't get moved out of scope, it is simply copied
+    // into `hashes`
+    let mut hashes = HASHES;
+    // for each chunk, schedule them and compress them
+    processed.chunks(64).for_each(|chunk| {
+        let scheduled = schedule(chunk);
+        compress(&mut hashes, &scheduled);
+    });
+
+    // digest the 8 u32 hash values that were
+    // successively modified in the chunk loop
+    //
+    // array_chunks_mut gives you a mutable reference to
+    // a [u8; 4] fixed array in which we can copy
+    // the i^th u32 of `hashes` converted into big
+    // endian bytes.
+    let mut digest = [0_u8; 32];
+    digest
+        .array_chunks_mut::<4>()
+        .enumerate()
+        .for_each(|(i, chunk)| chunk.copy_from_slice(&hashes[i].to_be_bytes()));
+
+    H256::new(digest)
+}
+
 /// Right rotates a 32 bit unsigned integer by a given number.
 ///
 /// Note, that without the modulo division and the if-else logic, the function
@@ -73,7 +83,7 @@ fn right_rotate(num: u32, mut by: usize) -> u32 {
     }
 }
 
-/// Preprocesses the input data.
+/// Preprocesses the input string, see [`pad`].
 ///
 /// A String type is a dynamic type allocated on the heap,
 /// therefore it doesn't implement the Copy trait. This means,
@@ -86,7 +96,14 @@ fn preprocess(input: String) -> Vec<u8> {
     // scope
     // into_bytes -> input is passed by value so it does get moved out of scope
     // and cannot be used afterwards
-    let mut input_bytes = input.into_bytes(); // Vec<u8>
+    pad(input.into_bytes())
+}
+
+/// Pads the input bytes to a multiple of 512 bits.
+///
+/// A single 1 bit is appended, followed by 0 bits, and the last 64 bits
+/// are replaced by the original length of the input in bits.
+fn pad(mut input_bytes: Vec<u8>) -> Vec<u8> {
     let original_bytes_len = input_bytes.len(); // in bytes!
 
     // reserve some memory for the Vec<u8> (dynamically allocated on the heap)
@@ -281,4 +298,26 @@ mod test {
             "fba4ec9f441ffbadbf3a21a9976976f34bf2448702c47279677ab594979a3bb9"
         );
     }
+
+    #[test]
+    fn encoding_bytes() {
+        let hasher = Sha256::new();
+
+        let encoded = hasher.digest_bytes(b"hello world");
+        assert_eq!(encoded, hasher.digest(String::from("hello world")));
+
+        // not valid utf8
+        let encoded = hasher.digest_bytes(&[0xff]);
+        assert_eq!(
+            encoded.to_string(),
+            "a8100ae6aa1940d0b663bb31cd466142ebbdbd5187131b92d93818987832eb89"
+        );
+
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        let encoded = hasher.digest_bytes(&bytes);
+        assert_eq!(
+            encoded.to_string(),
+            "40aff2e9d2d8922e47afd4648e6967497158785fbd1da870e7110266bf944880"
+        );
+    }
 }
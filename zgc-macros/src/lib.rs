@@ -0,0 +1,405 @@
+// Derive macros for the consensus encoding of zgc_common::encoding.
+//
+// Structs encode their fields in declaration order and enums encode a u8
+// tag followed by the fields of the variant. The encoding can be adjusted
+// with `#[encoding(...)]` attributes:
+//
+// - `version = N` on a struct or enum writes the version byte N first and
+//   rejects other versions when decoding, N can be any constant `u8`
+//   expression
+// - `order = N` on a field encodes the fields sorted by N instead of in
+//   declaration order, either every field or none has to be ordered
+// - `skip` on a field leaves it out of the encoding, it is decoded as
+//   `Default::default()`
+// - `tag = N` on an enum variant sets its tag, by default the tags are the
+//   indices of the variants
+//
+// The generated code refers to the `zgc_common` crate, which has to be a
+// dependency of the crate using the derives. The `hash!` and `address!`
+// literals are expanded in the `literal` module.
+
+mod literal;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, Fields, Ident, LitInt, LitStr,
+    Member, Result,
+};
+
+#[proc_macro_derive(Encode, attributes(encoding))]
+pub fn derive_encode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_encode(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Decode, attributes(encoding))]
+pub fn derive_decode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_decode(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// A `Hash<N>` from a hex literal with an optional `0x` prefix, `N` is half
+/// the number of hex digits.
+#[proc_macro]
+pub fn hash(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as LitStr);
+    literal::expand_hash(&input, None)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// An `Address` from a hex literal of 40 digits with an optional `0x`
+/// prefix. Mixed-case literals have to be checksummed, see
+/// `zgc_crypto::address::ChecksumAddress`.
+#[proc_macro]
+pub fn address(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as LitStr);
+    literal::expand_address(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// The options of an `#[encoding(...)]` attribute.
+#[derive(Default)]
+struct Options {
+    version: Option<Expr>,
+    order: Option<u32>,
+    tag: Option<u8>,
+    skip: bool,
+}
+
+impl Options {
+    fn parse(attributes: &[Attribute]) -> Result<Self> {
+        let mut options = Self::default();
+
+        for attribute in attributes {
+            if !attribute.path().is_ident("encoding") {
+                continue;
+            }
+
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("version") {
+                    options.version = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("order") {
+                    options.order = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                } else if meta.path.is_ident("tag") {
+                    options.tag = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                } else {
+                    return Err(meta.error("unknown encoding option"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(options)
+    }
+
+    /// Fails if an option is set that is not allowed at the position of the
+    /// attribute.
+    fn allow_only(&self, span: Span, version: bool, field: bool, tag: bool) -> Result<()> {
+        let misplaced = (!version && self.version.is_some())
+            || (!field && (self.order.is_some() || self.skip))
+            || (!tag && self.tag.is_some());
+
+        if misplaced {
+            return Err(Error::new(span, "encoding option is not allowed here"));
+        }
+        Ok(())
+    }
+}
+
+/// A field that is part of the encoding.
+struct EncodedField {
+    member: Member,
+    /// The variable the field is bound to in patterns and when decoding.
+    binding: Ident,
+}
+
+/// The fields of a struct or variant, split into the encoded fields in
+/// encoding order and the skipped ones.
+struct FieldList {
+    encoded: Vec<EncodedField>,
+    skipped: Vec<Member>,
+}
+
+impl FieldList {
+    fn new(fields: &Fields) -> Result<Self> {
+        let mut ordered = Vec::new();
+        let mut skipped = Vec::new();
+
+        for (i, field) in fields.iter().enumerate() {
+            let options = Options::parse(&field.attrs)?;
+            options.allow_only(field.span(), false, true, false)?;
+
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(i.into()),
+            };
+
+            if options.skip {
+                skipped.push(member);
+            } else {
+                let binding = format_ident!("field_{}", i);
+                ordered.push((
+                    options.order,
+                    field.span(),
+                    EncodedField { member, binding },
+                ));
+            }
+        }
+
+        let ordered_count = ordered
+            .iter()
+            .filter(|(order, _, _)| order.is_some())
+            .count();
+        if ordered_count > 0 {
+            if let Some((_, span, _)) = ordered.iter().find(|(order, _, _)| order.is_none()) {
+                return Err(Error::new(
+                    *span,
+                    "either every encoded field or none has to have an order",
+                ));
+            }
+
+            ordered.sort_by_key(|(order, _, _)| *order);
+            if let Some(pair) = ordered.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(Error::new(pair[1].1, "duplicate encoding order"));
+            }
+        }
+
+        Ok(Self {
+            encoded: ordered.into_iter().map(|(_, _, field)| field).collect(),
+            skipped,
+        })
+    }
+
+    /// The pattern binding every field of a variant, skipped fields are
+    /// ignored.
+    fn pattern(&self, fields: &Fields) -> TokenStream {
+        let bindings = self.encoded.iter().map(|field| {
+            let member = &field.member;
+            let binding = &field.binding;
+            quote!(#member: #binding)
+        });
+
+        match fields {
+            Fields::Unit => quote!(),
+            _ => quote!({ #(#bindings,)* .. }),
+        }
+    }
+
+    /// Encodes the bound fields in encoding order.
+    fn encode(&self) -> TokenStream {
+        let bindings = self.encoded.iter().map(|field| &field.binding);
+        quote! {
+            #(::zgc_common::encoding::Encode::encode(#bindings, writer)?;)*
+        }
+    }
+
+    /// Decodes the fields in encoding order and constructs `path` from
+    /// them.
+    fn decode(&self, path: TokenStream, fields: &Fields) -> TokenStream {
+        let bindings = self.encoded.iter().map(|field| &field.binding);
+        let encoded = self.encoded.iter().map(|field| {
+            let member = &field.member;
+            let binding = &field.binding;
+            quote!(#member: #binding)
+        });
+        let skipped = self.skipped.iter();
+
+        let construction = match fields {
+            Fields::Unit => quote!(#path),
+            _ => quote! {
+                #path {
+                    #(#encoded,)*
+                    #(#skipped: ::core::default::Default::default(),)*
+                }
+            },
+        };
+
+        quote! {
+            #(let #bindings = ::zgc_common::encoding::Decode::decode(reader)?;)*
+            #construction
+        }
+    }
+}
+
+fn version_encode(version: Option<Expr>) -> TokenStream {
+    match version {
+        Some(version) => quote! {
+            const VERSION: u8 = #version;
+            ::zgc_common::encoding::Encode::encode(&VERSION, writer)?;
+        },
+        None => quote!(),
+    }
+}
+
+fn version_decode(version: Option<Expr>) -> TokenStream {
+    match version {
+        Some(version) => quote! {
+            const VERSION: u8 = #version;
+            match <u8 as ::zgc_common::encoding::Decode>::decode(reader)? {
+                VERSION => {}
+                version => return ::core::result::Result::Err(
+                    ::zgc_common::Error::UnsupportedVersion(version),
+                ),
+            }
+        },
+        None => quote!(),
+    }
+}
+
+/// The tags of the variants of an enum, checked for duplicates.
+fn variant_tags(data: &syn::DataEnum) -> Result<Vec<u8>> {
+    if data.variants.len() > 256 {
+        return Err(Error::new(
+            data.variants.span(),
+            "enums with more than 256 variants are not supported",
+        ));
+    }
+
+    let mut tags: Vec<u8> = Vec::new();
+    for (i, variant) in data.variants.iter().enumerate() {
+        let options = Options::parse(&variant.attrs)?;
+        options.allow_only(variant.span(), false, false, true)?;
+
+        // cast is fine because there are at most 256 variants
+        let tag = options.tag.unwrap_or(i as u8);
+        if tags.contains(&tag) {
+            return Err(Error::new(variant.span(), "duplicate encoding tag"));
+        }
+        tags.push(tag);
+    }
+
+    Ok(tags)
+}
+
+/// Adds a `bound` bound to every type parameter.
+fn generics(input: &DeriveInput, bound: TokenStream) -> syn::Generics {
+    let mut generics = input.generics.clone();
+    for parameter in generics.type_params_mut() {
+        parameter.bounds.push(syn::parse2(bound.clone()).unwrap());
+    }
+    generics
+}
+
+fn expand_encode(input: &DeriveInput) -> Result<TokenStream> {
+    let options = Options::parse(&input.attrs)?;
+    options.allow_only(input.span(), true, false, false)?;
+    let version = version_encode(options.version);
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = FieldList::new(&data.fields)?;
+            let pattern = fields.pattern(&data.fields);
+            let encode = fields.encode();
+            quote! {
+                let Self #pattern = self;
+                #encode
+            }
+        }
+        Data::Enum(data) => {
+            let tags = variant_tags(data)?;
+            let arms = data
+                .variants
+                .iter()
+                .zip(tags)
+                .map(|(variant, tag)| {
+                    let fields = FieldList::new(&variant.fields)?;
+                    let name = &variant.ident;
+                    let pattern = fields.pattern(&variant.fields);
+                    let encode = fields.encode();
+                    Ok(quote! {
+                        Self::#name #pattern => {
+                            ::zgc_common::encoding::Encode::encode(&#tag, writer)?;
+                            #encode
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "unions cannot be encoded",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let generics = generics(input, quote!(::zgc_common::encoding::Encode));
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::zgc_common::encoding::Encode for #name #type_generics #where_clause {
+            fn encode<W: ::std::io::Write>(&self, writer: &mut W) -> ::std::io::Result<()> {
+                #version
+                #body
+                ::core::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+fn expand_decode(input: &DeriveInput) -> Result<TokenStream> {
+    let options = Options::parse(&input.attrs)?;
+    options.allow_only(input.span(), true, false, false)?;
+    let version = version_decode(options.version);
+
+    let body = match &input.data {
+        Data::Struct(data) => FieldList::new(&data.fields)?.decode(quote!(Self), &data.fields),
+        Data::Enum(data) => {
+            let tags = variant_tags(data)?;
+            let arms = data
+                .variants
+                .iter()
+                .zip(tags)
+                .map(|(variant, tag)| {
+                    let fields = FieldList::new(&variant.fields)?;
+                    let name = &variant.ident;
+                    let decode = fields.decode(quote!(Self::#name), &variant.fields);
+                    Ok(quote!(#tag => { #decode }))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            quote! {
+                match <u8 as ::zgc_common::encoding::Decode>::decode(reader)? {
+                    #(#arms)*
+                    tag => return ::core::result::Result::Err(::zgc_common::Error::InvalidTag(tag)),
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                "unions cannot be decoded",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let generics = generics(input, quote!(::zgc_common::encoding::Decode));
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::zgc_common::encoding::Decode for #name #type_generics #where_clause {
+            fn decode<R: ::std::io::Read>(reader: &mut R) -> ::core::result::Result<Self, ::zgc_common::Error> {
+                #version
+                ::core::result::Result::Ok({ #body })
+            }
+        }
+    })
+}
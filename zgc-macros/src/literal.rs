@@ -0,0 +1,122 @@
+// Hash literals that are checked at compile time.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use sha2::{Digest, Sha256};
+use syn::{Error, LitStr, Result};
+
+/// Parses the hex digits of `literal` into a `Hash<N>` expression, if
+/// `expected` is given the literal has to have exactly that many bytes.
+pub fn expand_hash(literal: &LitStr, expected: Option<usize>) -> Result<TokenStream> {
+    let value = literal.value();
+    let digits = value.strip_prefix("0x").unwrap_or(&value);
+
+    if let Some((position, character)) = digits
+        .chars()
+        .enumerate()
+        .find(|(_, character)| !character.is_ascii_hexdigit())
+    {
+        return Err(Error::new(
+            literal.span(),
+            format!(
+                "invalid hex character {:?} at position {}",
+                character, position
+            ),
+        ));
+    }
+
+    let length_error = match expected {
+        Some(bytes) if digits.len() != 2 * bytes => Some(format!(
+            "input length was {}, expected {}",
+            digits.len(),
+            2 * bytes
+        )),
+        _ if digits.len() % 2 == 1 => Some(format!(
+            "input length was {}, expected an even number of hex digits",
+            digits.len()
+        )),
+        _ => None,
+    };
+    if let Some(message) = length_error {
+        return Err(Error::new(literal.span(), message));
+    }
+
+    // every character is an ASCII hex digit, so the string splits into
+    // pairs of digits at any even index
+    let bytes = (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap());
+
+    Ok(quote! {
+        ::zgc_common::Hash::new([#(#bytes),*])
+    })
+}
+
+/// Parses an address literal like [`expand_hash`]. All-lowercase and
+/// all-uppercase literals carry no checksum, mixed-case literals have to be
+/// in the checksummed form.
+pub fn expand_address(literal: &LitStr) -> Result<TokenStream> {
+    let tokens = expand_hash(literal, Some(20))?;
+
+    let value = literal.value();
+    let digits = value.strip_prefix("0x").unwrap_or(&value);
+    let has_lowercase = digits.chars().any(|c| c.is_ascii_lowercase());
+    let has_uppercase = digits.chars().any(|c| c.is_ascii_uppercase());
+    if !(has_lowercase && has_uppercase) {
+        return Ok(tokens);
+    }
+
+    let checksummed = checksum(&digits.to_ascii_lowercase());
+    if let Some((position, (found, expected))) = digits
+        .chars()
+        .zip(checksummed.chars())
+        .enumerate()
+        .find(|(_, (found, expected))| found != expected)
+    {
+        return Err(Error::new(
+            literal.span(),
+            format!(
+                "invalid checksum at position {}: expected {:?}, found {:?}",
+                position, expected, found
+            ),
+        ));
+    }
+    Ok(tokens)
+}
+
+/// The checksummed form of lowercase hex digits, the same as
+/// `zgc_crypto::address::ChecksumAddress`.
+fn checksum(lowercase: &str) -> String {
+    let hash = Sha256::digest(lowercase.as_bytes());
+    lowercase
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let byte = hash[i / 2];
+            let nibble = if i.is_multiple_of(2) {
+                byte >> 4
+            } else {
+                byte & 0x0f
+            };
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checksum_matches_address() {
+        // the checksummed address of the zgc-crypto address tests
+        assert_eq!(
+            checksum("0123456789abcdeffedcba9876543210aabbccdd"),
+            "0123456789AbcDeFFEdcBA9876543210AaBBccdD"
+        );
+    }
+}
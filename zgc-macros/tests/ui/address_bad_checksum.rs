@@ -0,0 +1,3 @@
+fn main() {
+    let _ = zgc_common::address!("0123456789AbcDeFFEdcBA9876543210AaBBccdd");
+}
@@ -0,0 +1,3 @@
+fn main() {
+    let _ = zgc_common::hash!("00ff0g");
+}
@@ -0,0 +1,3 @@
+fn main() {
+    let _ = zgc_common::hash!("0xabc");
+}